@@ -0,0 +1,241 @@
+//! Conversion of gregorian dates to other calendars, to display them alongside the stored date.
+//! The algorithms are the arithmetic ones from "Calendrical Calculations" by Reingold and Dershowitz,
+//! working on fixed day numbers (1 being january 1st of the year 1).
+
+use chrono::{Datelike, NaiveDate};
+use clap::ValueEnum;
+
+/// A calendar that dates can be displayed in
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq)]
+pub enum Calendar {
+    Hebrew,
+    /// The arithmetic islamic calendar, which can be a day or two apart from the observed one
+    Islamic,
+}
+
+impl Calendar {
+    /// The english name of the calendar
+    pub fn name(&self) -> &'static str {
+        match self {
+            Calendar::Hebrew => "Hebrew",
+            Calendar::Islamic => "Islamic",
+        }
+    }
+
+    /// Format the date in the calendar, such as "23 Tevet 5760" or "24 Ramadan 1420 AH"
+    pub fn format(&self, date: NaiveDate) -> String {
+        match self {
+            Calendar::Hebrew => {
+                let (year, month, day) = hebrew_from_date(date);
+                format!("{} {} {}", day, hebrew_month_name(month, year), year)
+            }
+            Calendar::Islamic => {
+                let (year, month, day) = islamic_from_date(date);
+                format!("{} {} {} AH", day, ISLAMIC_MONTHS[month as usize - 1], year)
+            }
+        }
+    }
+}
+
+/// The fixed day number of a gregorian date
+fn fixed_from_date(date: NaiveDate) -> i64 {
+    i64::from(date.num_days_from_ce())
+}
+
+/// The fixed day number of the 1st of Muharram of the year 1 (july 16th, 622 in the julian calendar)
+const ISLAMIC_EPOCH: i64 = 227015;
+
+const ISLAMIC_MONTHS: [&str; 12] = [
+    "Muharram",
+    "Safar",
+    "Rabi' al-Awwal",
+    "Rabi' al-Thani",
+    "Jumada al-Awwal",
+    "Jumada al-Thani",
+    "Rajab",
+    "Sha'ban",
+    "Ramadan",
+    "Shawwal",
+    "Dhu al-Qa'dah",
+    "Dhu al-Hijjah",
+];
+
+fn fixed_from_islamic(year: i64, month: u32, day: u32) -> i64 {
+    let month = i64::from(month);
+    i64::from(day)
+        + 29 * (month - 1)
+        + (6 * month - 1).div_euclid(11)
+        + (year - 1) * 354
+        + (3 + 11 * year).div_euclid(30)
+        + ISLAMIC_EPOCH
+        - 1
+}
+
+/// Get the year, month (from 1 to 12) and day of a date in the arithmetic islamic calendar
+pub fn islamic_from_date(date: NaiveDate) -> (i64, u32, u32) {
+    let fixed = fixed_from_date(date);
+    let year = (30 * (fixed - ISLAMIC_EPOCH) + 10646).div_euclid(10631);
+    let prior_days = fixed - fixed_from_islamic(year, 1, 1);
+    let month = (11 * prior_days + 330).div_euclid(325) as u32;
+    let day = (fixed - fixed_from_islamic(year, month, 1) + 1) as u32;
+    (year, month, day)
+}
+
+/// The fixed day number of the 1st of Tishrei of the year 1 (october 7th, 3761 BCE in the julian calendar)
+const HEBREW_EPOCH: i64 = -1373427;
+
+/// Hebrew months are numbered from Nisan, even though the year starts in Tishrei
+const NISAN: u32 = 1;
+const TISHREI: u32 = 7;
+
+fn is_hebrew_leap_year(year: i64) -> bool {
+    (7 * year + 1).rem_euclid(19) < 7
+}
+
+/// Leap years have a 13th month, Adar II
+fn last_month_of_hebrew_year(year: i64) -> u32 {
+    if is_hebrew_leap_year(year) {
+        13
+    } else {
+        12
+    }
+}
+
+fn hebrew_month_name(month: u32, year: i64) -> &'static str {
+    match month {
+        1 => "Nisan",
+        2 => "Iyar",
+        3 => "Sivan",
+        4 => "Tammuz",
+        5 => "Av",
+        6 => "Elul",
+        7 => "Tishrei",
+        8 => "Heshvan",
+        9 => "Kislev",
+        10 => "Tevet",
+        11 => "Shevat",
+        12 if is_hebrew_leap_year(year) => "Adar I",
+        12 => "Adar",
+        13 => "Adar II",
+        _ => panic!("Invalid hebrew month: {}", month),
+    }
+}
+
+/// The number of days from the epoch to the molad of Tishrei of the year, moved by the weekday rules
+fn hebrew_calendar_elapsed_days(year: i64) -> i64 {
+    let months_elapsed = (235 * year - 234).div_euclid(19);
+    let parts_elapsed = 12084 + 13753 * months_elapsed;
+    let days = 29 * months_elapsed + parts_elapsed.div_euclid(25920);
+    // The new year can't be on a sunday, wednesday or friday
+    if (3 * (days + 1)).rem_euclid(7) < 3 {
+        days + 1
+    } else {
+        days
+    }
+}
+
+/// The delay of the new year that keeps the length of the year and the previous one valid
+fn hebrew_year_length_correction(year: i64) -> i64 {
+    let previous = hebrew_calendar_elapsed_days(year - 1);
+    let current = hebrew_calendar_elapsed_days(year);
+    let next = hebrew_calendar_elapsed_days(year + 1);
+    if next - current == 356 {
+        2
+    } else if current - previous == 382 {
+        1
+    } else {
+        0
+    }
+}
+
+fn hebrew_new_year(year: i64) -> i64 {
+    HEBREW_EPOCH + hebrew_calendar_elapsed_days(year) + hebrew_year_length_correction(year)
+}
+
+fn days_in_hebrew_year(year: i64) -> i64 {
+    hebrew_new_year(year + 1) - hebrew_new_year(year)
+}
+
+fn last_day_of_hebrew_month(month: u32, year: i64) -> u32 {
+    let days_in_year = days_in_hebrew_year(year);
+    let short = match month {
+        2 | 4 | 6 | 10 | 13 => true,
+        12 => !is_hebrew_leap_year(year),
+        // Heshvan is long and Kislev is short in some years, to adjust the length of the year
+        8 => days_in_year % 10 != 5,
+        9 => days_in_year % 10 == 3,
+        _ => false,
+    };
+    if short {
+        29
+    } else {
+        30
+    }
+}
+
+fn fixed_from_hebrew(year: i64, month: u32, day: u32) -> i64 {
+    let days_in_months = |months: &mut dyn Iterator<Item = u32>| -> i64 {
+        months
+            .map(|month| i64::from(last_day_of_hebrew_month(month, year)))
+            .sum()
+    };
+    let months_before = if month < TISHREI {
+        days_in_months(&mut (TISHREI..=last_month_of_hebrew_year(year)))
+            + days_in_months(&mut (NISAN..month))
+    } else {
+        days_in_months(&mut (TISHREI..month))
+    };
+    hebrew_new_year(year) + i64::from(day) - 1 + months_before
+}
+
+/// Get the year, month (from 1 for Nisan to 13 for Adar II) and day of a date in the hebrew calendar
+pub fn hebrew_from_date(date: NaiveDate) -> (i64, u32, u32) {
+    let fixed = fixed_from_date(date);
+    // 35975351 / 98496 is the average length of a year
+    let approx = ((fixed - HEBREW_EPOCH) * 98496).div_euclid(35975351) + 1;
+    let year = if hebrew_new_year(approx) <= fixed {
+        approx
+    } else {
+        approx - 1
+    };
+    let mut month = if fixed < fixed_from_hebrew(year, NISAN, 1) {
+        TISHREI
+    } else {
+        NISAN
+    };
+    while fixed > fixed_from_hebrew(year, month, last_day_of_hebrew_month(month, year)) {
+        month += 1;
+    }
+    let day = (fixed - fixed_from_hebrew(year, month, 1) + 1) as u32;
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{hebrew_from_date, islamic_from_date, Calendar};
+    use chrono::NaiveDate;
+    use test_case::test_case;
+
+    #[test_case(NaiveDate::from_ymd_opt(2000, 1, 1).unwrap(), (5760, 10, 23) ; "23 Tevet 5760")]
+    #[test_case(NaiveDate::from_ymd_opt(2024, 10, 2).unwrap(), (5784, 6, 29) ; "Last day of the year")]
+    #[test_case(NaiveDate::from_ymd_opt(2024, 10, 3).unwrap(), (5785, 7, 1) ; "Rosh Hashanah 5785")]
+    #[test_case(NaiveDate::from_ymd_opt(2023, 3, 7).unwrap(), (5783, 12, 14) ; "Purim on a common year")]
+    #[test_case(NaiveDate::from_ymd_opt(2024, 3, 24).unwrap(), (5784, 13, 14) ; "Purim on a leap year")]
+    #[test_case(NaiveDate::from_ymd_opt(2024, 7, 7).unwrap(), (5784, 4, 1) ; "1 Tammuz 5784")]
+    fn test_hebrew_from_date(date: NaiveDate, expected: (i64, u32, u32)) {
+        assert_eq!(hebrew_from_date(date), expected);
+    }
+
+    #[test_case(NaiveDate::from_ymd_opt(2000, 1, 1).unwrap(), (1420, 9, 24) ; "24 Ramadan 1420")]
+    #[test_case(NaiveDate::from_ymd_opt(622, 7, 19).unwrap(), (1, 1, 1) ; "Epoch")]
+    #[test_case(NaiveDate::from_ymd_opt(2024, 7, 7).unwrap(), (1445, 12, 30) ; "Last day of a leap year")]
+    fn test_islamic_from_date(date: NaiveDate, expected: (i64, u32, u32)) {
+        assert_eq!(islamic_from_date(date), expected);
+    }
+
+    #[test_case(Calendar::Hebrew, NaiveDate::from_ymd_opt(2024, 3, 24).unwrap(), "14 Adar II 5784" ; "Hebrew")]
+    #[test_case(Calendar::Islamic, NaiveDate::from_ymd_opt(2000, 1, 1).unwrap(), "24 Ramadan 1420 AH" ; "Islamic")]
+    fn test_format(calendar: Calendar, date: NaiveDate, expected: &str) {
+        assert_eq!(calendar.format(date), expected);
+    }
+}
@@ -1,8 +1,9 @@
 use std::path::PathBuf;
 
-use crate::config::BirthdayDate;
-use chrono_tz::Tz;
-use clap::{Parser, Subcommand};
+use crate::calendar::Calendar;
+use crate::config::{BirthdayDate, EntryKind, EntryTimezone, TableStyle};
+use chrono::{DateTime, NaiveTime, Utc};
+use clap::{ArgGroup, Args, Parser, Subcommand, ValueEnum};
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
@@ -10,36 +11,645 @@ pub struct Cli {
     #[clap(subcommand)]
     pub command: Commands,
 
-    /// The birthday file to use
-    #[arg(short, long)]
-    pub file: Option<PathBuf>,
+    /// The birthday file to use, or "-" to read it from stdin (only for commands that don't modify it).
+    /// Can be used multiple times to list the entries of several files together,
+    /// the other settings are taken from the first one
+    #[arg(short, long, env = "BDAY_FILE")]
+    pub file: Vec<PathBuf>,
+
+    /// Display the time of birthdays in UTC instead of the local timezone
+    #[arg(long, global = true)]
+    pub utc: bool,
+
+    /// Exit with code 1 if no entries are displayed by list, agenda, greeting or milestones
+    #[arg(long, global = true)]
+    pub strict: bool,
+
+    /// Pretend that the current time is the given one (such as 2024-12-24T09:00:00+01:00),
+    /// to get the same output on every run
+    #[arg(long, global = true, hide = true, value_name = "RFC3339", value_parser = parse_now)]
+    pub now: Option<DateTime<Utc>>,
+
+    /// How errors are printed on stderr, "json" giving objects such as {"error": "...", "kind": "io"}
+    /// for programs running bday. The kind is one of config (the birthday file can't be read, parsed
+    /// or modified), timezone (the timezone of an entry is invalid), io (writing files, running other
+    /// programs or reading answers failed), value (a given value can't be used, such as the name of
+    /// an entry that doesn't exist) or usage (the command is invalid). The exit codes are the same
+    #[arg(long, global = true, value_enum, default_value_t = ErrorFormat::Text)]
+    pub error_format: ErrorFormat,
+}
+
+/// How errors are printed
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq)]
+pub enum ErrorFormat {
+    /// Messages for humans
+    Text,
+    /// JSON objects with the message as "error" and its category as "kind"
+    /// (config, timezone, io, value or usage)
+    Json,
+}
+
+/// Whether the arguments ask for JSON errors, to use when they can't be parsed
+pub fn json_errors_requested(args: impl Iterator<Item = String>) -> bool {
+    let args: Vec<String> = args.collect();
+    args.iter().any(|arg| arg == "--error-format=json")
+        || args
+            .windows(2)
+            .any(|pair| pair[0] == "--error-format" && pair[1] == "json")
 }
 
 #[derive(Subcommand, Debug)]
 pub enum Commands {
     /// Adds a new entry
     Add {
-        /// The name associated with the entry
+        /// The name associated with the entry, asked for if it isn't given
+        #[arg(value_name = "NAME")]
+        name_positional: Option<String>,
+
+        /// The date associated with the entry, asked for if it isn't given
+        #[arg(value_name = "DATE")]
+        date_positional: Option<BirthdayDate>,
+
+        /// The name associated with the entry, instead of NAME
+        #[arg(short, long, conflicts_with = "name_positional")]
+        name: Option<String>,
+
+        /// The date associated with the entry, instead of DATE
+        #[arg(short, long, conflicts_with = "date_positional")]
+        date: Option<BirthdayDate>,
+
+        /// Allow a year of birth after the current year
+        #[arg(long)]
+        allow_future: bool,
+
+        /// Optional timezone for the entry, either a name like "Asia/Kolkata" or a UTC offset like "+05:30"
+        #[clap(short, long)]
+        timezone: Option<EntryTimezone>,
+
+        /// Hide the entry from the list, unless --all is used
+        #[arg(long)]
+        hidden: bool,
+
+        /// Another name the entry can be found by, can be used multiple times
+        #[arg(short, long = "alias")]
+        aliases: Vec<String>,
+
+        /// The first name of the person, displayed after the last name with list --reverse-name
+        #[arg(long)]
+        first_name: Option<String>,
+
+        /// The last name of the person, to display the name as "Last, First" with list --reverse-name
+        /// and to sort entries by last name with list --sort name
+        #[arg(long)]
+        last_name: Option<String>,
+
+        /// How much to spend on a gift for the entry
         #[arg(short, long)]
-        name: String,
+        budget: Option<String>,
 
-        /// The date associated with the entry
+        /// The path to a picture of the person
         #[arg(short, long)]
-        date: BirthdayDate,
+        photo: Option<PathBuf>,
 
-        /// Optional timezone for the entry
-        #[clap(short, long)]
-        #[clap(value_parser = Tz::from_str_insensitive)]
-        timezone: Option<Tz>,
+        /// The email address of the person, for the mailto link of show and greeting
+        #[arg(short, long)]
+        email: Option<String>,
+
+        /// The relationship with the person, used to pick the greeting message
+        #[arg(short, long)]
+        relationship: Option<String>,
+
+        /// Whether the date is a birthday or the anniversary of an event, such as a wedding
+        #[arg(long, value_enum, default_value_t = EntryKind::Birthday)]
+        kind: EntryKind,
+
+        /// The time at which the birthday starts in the timezone of the entry (HH:MM), instead of midnight
+        #[arg(long)]
+        time: Option<NaiveTime>,
     },
-    // TODO: Add option to show raw timezone instead of duration until the birthday
     /// Lists entries
-    List {
-        /// Display only the closest n entries
+    List(ListArgs),
+    /// Lists entries again every few seconds, until stopped with Ctrl-C
+    Watch {
+        /// The number of seconds between each refresh
+        #[arg(short, long, default_value_t = 60)]
+        interval: u64,
+
+        #[command(flatten)]
+        list: ListArgs,
+    },
+    /// Prints a greeting message for each birthday happening today
+    Greeting {
+        /// Also start an email with the greeting message for the entries with an email address,
+        /// in the default mail client
+        #[arg(long)]
+        open: bool,
+
+        /// Put the greeting messages on the clipboard instead of printing them,
+        /// they are still printed if no clipboard is available
+        #[arg(long)]
+        copy: bool,
+    },
+    /// Prints the next dates on which birthdays happen, with the people born on each of them
+    Agenda {
+        /// The number of dates to print
+        #[arg(short, long, default_value_t = 5)]
+        count: usize,
+    },
+    /// Prints the upcoming dates on which people will have lived a round number of days (10000, 15000...),
+    /// for the entries with a known year of birth
+    Milestones {
+        /// How many days ahead to look for milestones
+        #[arg(short, long, default_value_t = 365)]
+        within: i64,
+    },
+    /// Prints statistics about the entries: how many there are, how many have a year of birth,
+    /// the average age, the oldest person and the number of entries in each month
+    Stats {
+        /// Print the statistics as a JSON object instead of tables, to keep track of them over time
+        #[arg(long)]
+        json: bool,
+        /// Also count hidden entries
+        #[arg(short, long)]
+        all: bool,
+    },
+    /// Prints the difference in age between two entries with a known date of birth, and who is older
+    Gap {
+        /// The name or alias of the first entry
+        a: String,
+
+        /// The name or alias of the second entry
+        b: String,
+    },
+    /// Prints the entries born on the given day of the year, however far it is, with the age they turn on it
+    On {
+        /// The date to look for, only its day and month are compared (DD/MM, MM-DD, or with a year to get
+        /// the ages on that date)
+        date: BirthdayDate,
+    },
+    /// Shows the details of an entry
+    Show {
+        /// The name or alias of the entry, or a part of it, hidden entries are included
+        name: String,
+
+        /// Only show the entries whose whole name or alias is the given name, instead of those containing it
+        #[arg(long)]
+        match_exact: bool,
+
+        /// Start an email to the entry in the default mail client, if it has an email address
+        #[arg(long)]
+        open: bool,
+
+        /// Also display how old the person will be (or was) on the given date
+        #[arg(long, value_name = "DATE")]
+        age_on: Option<BirthdayDate>,
+    },
+    /// Renames an existing entry
+    Rename {
+        /// The current name of the entry, which must be given exactly
+        from: String,
+
+        /// The new name of the entry
+        to: String,
+
+        /// Which entry to rename if several share the same name (starting from 1)
+        #[arg(short, long)]
+        index: Option<usize>,
+    },
+    /// Rewrites the birthday file in a canonical form, sorted by date
+    Fmt,
+    /// Removes all the entries matching the given criteria, after asking for confirmation
+    #[command(group(ArgGroup::new("criteria").required(true).multiple(true)))]
+    Prune {
+        /// Remove the hidden entries
+        #[arg(long, group = "criteria")]
+        hidden: bool,
+
+        /// Remove the entries born before the given year (entries of unknown year are kept)
+        #[arg(long, value_name = "YEAR", group = "criteria")]
+        before_year: Option<i32>,
+
+        /// Don't ask for confirmation
         #[arg(short, long)]
-        limit: Option<usize>,
-        /// Display only entries that will happen before (or during) the given date
+        yes: bool,
+    },
+    /// Reverts the last change to the birthday file, using the backup made before it.
+    /// Running it again redoes the change
+    Undo,
+    /// Prints entries in the given format
+    Export {
+        /// The format to print the entries in
+        #[arg(long, value_enum, default_value_t = ExportFormat::Toml)]
+        format: ExportFormat,
+        /// The columns of the CSV format
+        #[arg(long, value_enum, default_value_t = CsvSchema::Generic)]
+        schema: CsvSchema,
+        /// The character separating the fields of the CSV format, such as ";" for spreadsheets
+        /// in locales using the comma as decimal separator ("\t" for tabs)
+        #[arg(long, value_parser = parse_separator, default_value = ",")]
+        separator: u8,
+        /// Export only entries that will happen after (or during) the given date
+        #[arg(long)]
+        since: Option<BirthdayDate>,
+        /// Export only entries that will happen before (or during) the given date
+        #[arg(long)]
+        until: Option<BirthdayDate>,
+        /// Also export hidden entries
         #[arg(short, long)]
-        before: Option<BirthdayDate>,
+        all: bool,
     },
+    /// Adds the entries of a CSV file, with the columns name, day, month, year and timezone
+    Import {
+        /// The CSV file to import, such as one made with `export --format csv`
+        file: PathBuf,
+
+        /// The character separating the fields of the file, as given to `export --separator`
+        #[arg(long, value_parser = parse_separator, default_value = ",")]
+        separator: u8,
+
+        /// Only check that the rows are valid, without adding them
+        #[arg(long, visible_alias = "dry-run")]
+        check: bool,
+
+        /// Allow years of birth after the current year, which make the rows invalid otherwise
+        #[arg(long)]
+        allow_future: bool,
+
+        /// Don't ask for confirmation when importing more entries than 'import.confirm_above'
+        #[arg(short, long)]
+        yes: bool,
+    },
+    /// Prints the path of the birthday file that is used
+    Where,
+    /// Lists randomly generated entries, without using the birthday file
+    #[command(hide = true)]
+    Demo {
+        /// The number of entries to generate
+        #[arg(short, long, default_value_t = 10)]
+        count: usize,
+
+        /// The seed used to generate the entries, to always get the same ones
+        #[arg(long)]
+        seed: Option<u64>,
+
+        #[command(flatten)]
+        list: ListArgs,
+    },
+}
+
+#[derive(Args, Debug)]
+pub struct ListArgs {
+    /// Display only the closest n entries (at least 1)
+    #[arg(short, long, value_parser = parse_limit)]
+    pub limit: Option<usize>,
+    /// Display only the closest birthday, out of the entries matching the other filters
+    #[arg(long, conflicts_with_all = ["limit", "last"])]
+    pub first: bool,
+    /// Display only the farthest birthday, out of the entries matching the other filters
+    #[arg(long, conflicts_with = "limit")]
+    pub last: bool,
+    /// Display only entries that will happen strictly before the given date, which can't be in the past.
+    /// Unlike --until, entries happening on the date itself are left out
+    #[arg(short, long)]
+    pub before: Option<BirthdayDate>,
+    /// Display only entries that will happen after (or during) the given date
+    #[arg(long)]
+    pub since: Option<BirthdayDate>,
+    /// Display only entries that will happen before or during the given date, which is included unlike with --before.
+    /// Used with --since to display the entries of a range of dates
+    #[arg(long)]
+    pub until: Option<BirthdayDate>,
+    /// Display the entries as they will be on the given date, instead of today
+    #[arg(long, value_name = "DATE")]
+    pub relative_to: Option<BirthdayDate>,
+    /// Display only entries of the given month (by number or name), sorted by day
+    #[arg(short, long, value_parser = parse_month)]
+    pub month: Option<u32>,
+    /// Display only entries in the given timezone, either a name like "Europe/Paris" or a UTC offset like "+01:00"
+    #[arg(long)]
+    pub timezone: Option<EntryTimezone>,
+    /// With --timezone, also display the entries that don't have a timezone
+    #[arg(long, requires = "timezone")]
+    pub include_local: bool,
+    /// Display how many entries were shown out of the total, if some were left out
+    #[arg(short, long)]
+    pub summary: bool,
+    /// Also display hidden entries
+    #[arg(short, long)]
+    pub all: bool,
+    /// Display only entries whose birthday is at most the given number of days away,
+    /// counted in the timezone of each entry (see --sort days)
+    #[arg(short, long, value_name = "DAYS")]
+    pub within: Option<i64>,
+    /// Display half birthdays (6 months after the birthday) instead of birthdays.
+    /// Entries on a weekday of the month keep their week and weekday, such as the second sunday of november
+    /// for the second sunday of may, and entries without a day only change month
+    #[arg(long)]
+    pub half: bool,
+    /// Display the names as "Last, First" for the entries with a last name, such as for a formal roster
+    /// sorted with --sort name
+    #[arg(long)]
+    pub reverse_name: bool,
+    /// Display the age in years and months for entries younger than the given number of years
+    #[arg(long, value_name = "YEARS", num_args = 0..=1, default_missing_value = "2")]
+    pub precise_age: Option<i32>,
+    /// Display the current age in words, such as "45 years old", instead of the ages before and after the next birthday
+    #[arg(long, conflicts_with = "half")]
+    pub relative: bool,
+    /// Display the gift budget of the entries
+    #[arg(long)]
+    pub budget: bool,
+    /// Display the birthstone of the entries
+    #[arg(long)]
+    pub birthstone: bool,
+    /// Display the day of the week the entries were born on
+    #[arg(long)]
+    pub born: bool,
+    /// Display the timezone stored for the entries, or "local" for those without one
+    #[arg(long)]
+    pub show_timezone: bool,
+    /// Display the date of birth in another calendar, for entries with a known year of birth
+    #[arg(long, value_enum, value_name = "CALENDAR")]
+    pub calendar_display: Option<Calendar>,
+    /// Display the entries with the given name in bold yellow, ignoring case and accents, can be used multiple times
+    #[arg(long, value_name = "NAME")]
+    pub highlight: Vec<String>,
+    /// Also display in bold yellow the entries turning a milestone age on their next birthday,
+    /// the ages of the 'options.milestones' key of the birthday file or all the multiples of 10
+    #[arg(long)]
+    pub milestones: bool,
+    /// The borders of the table, instead of the 'display.table_style' key of the birthday file
+    #[arg(long, value_enum)]
+    pub table_style: Option<TableStyle>,
+    /// Don't display the titles of the columns, for example to use the table in other tools with --table-style clean
+    #[arg(long)]
+    pub no_header: bool,
+    /// How dates are displayed in the Date column and with --oneline
+    #[arg(long, value_enum, default_value_t = DateFormat::Text)]
+    pub format: DateFormat,
+    /// Display the dates without leading zeros and with an ordinal day, such as "Dec 3rd", the same as
+    /// --format compact
+    #[arg(long, conflicts_with = "format")]
+    pub compact_date: bool,
+    /// Display all the optional columns (the budget, birthstone, weekday of birth, timezone, and the calendar of
+    /// --calendar-display if it is used), wrapping the long budgets over several lines
+    #[arg(long, conflicts_with_all = ["columns", "oneline"])]
+    pub wide: bool,
+    /// Display each entry on a single line instead of in a table
+    #[arg(long)]
+    pub oneline: bool,
+    /// With --oneline, the text between the date and the name of the entries
+    #[arg(long, requires = "oneline", default_value = " — ")]
+    pub separator: String,
+    /// Display each entry as tab-separated fields for scripts: the name, the date of the next birthday
+    /// (YYYY-MM-DD) and the number of days until it, in the timezone of the entry.
+    /// This format is stable and won't change in future versions
+    #[arg(long, conflicts_with_all = ["oneline", "group_by", "summary"])]
+    pub porcelain: bool,
+    /// Display each entry as a JSON object on its own line (NDJSON), with its name, date, kind,
+    /// the date of the next birthday, the number of days until it, and the age turned on it.
+    /// The objects are the same as the entries of --json
+    #[arg(long, conflicts_with_all = ["oneline", "group_by", "summary", "porcelain"])]
+    pub json_lines: bool,
+    /// Display the entries as a single JSON object, {"total": N, "shown": K, "entries": [...]},
+    /// where total is the number of entries before --limit and the filters, so that scripts know if some were left out
+    #[arg(long, conflicts_with_all = ["oneline", "group_by", "summary", "porcelain", "json_lines"])]
+    pub json: bool,
+    /// Display a seasonal emoji before the date of the entries
+    #[arg(long)]
+    pub festive: bool,
+    /// The columns to display, in order, separated by commas (overrides the options adding columns)
+    #[arg(long, value_delimiter = ',')]
+    pub columns: Option<Vec<Column>>,
+    /// Display entries in a separate table for each group
+    #[arg(long, value_enum)]
+    pub group_by: Option<GroupBy>,
+    /// What entries are sorted by
+    #[arg(long, value_enum, default_value_t = SortBy::Date)]
+    pub sort: SortBy,
+    /// The direction entries are sorted in, entries that are equal for the sort key are sorted by date in that direction too
+    #[arg(long, value_enum, default_value_t = SortDir::Asc)]
+    pub sort_dir: SortDir,
+    /// Where entries with an unknown year of birth are displayed when sorting by age, whatever the sort direction
+    #[arg(long, value_enum, default_value_t = UnknownAge::Last)]
+    pub unknown_age: UnknownAge,
+}
+
+/// Parse a month from its number or its english name
+fn parse_month(s: &str) -> Result<u32, String> {
+    match s.parse::<u32>() {
+        Ok(month) if (1..=12).contains(&month) => Ok(month),
+        Ok(month) => Err(format!(
+            "month {} is out of range, it must be between 1 and 12",
+            month
+        )),
+        Err(_) => s
+            .parse::<chrono::Month>()
+            .map(|month| month.number_from_month())
+            .map_err(|_| format!("'{}' is not a month", s)),
+    }
+}
+
+/// Parse a limit on the number of entries, which can't be 0 as it would display nothing
+fn parse_limit(s: &str) -> Result<usize, String> {
+    match s.parse::<usize>() {
+        Ok(0) => Err(
+            "the limit must be at least 1, don't use --limit to display all entries".to_string(),
+        ),
+        Ok(limit) => Ok(limit),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Parse the separator of CSV exports, which must be a single ASCII character
+fn parse_separator(s: &str) -> Result<u8, String> {
+    match s.as_bytes() {
+        b"\\t" => Ok(b'\t'),
+        [c] if c.is_ascii() => Ok(*c),
+        _ => Err("the separator must be a single ASCII character, such as ';'".to_string()),
+    }
+}
+
+/// Parse the instant given to --now, as an RFC 3339 date and time with its UTC offset
+fn parse_now(s: &str) -> Result<DateTime<Utc>, String> {
+    DateTime::parse_from_rfc3339(s)
+        .map(|now| now.with_timezone(&Utc))
+        .map_err(|e| {
+            format!(
+                "{} (expected a date and time such as 2024-12-24T09:00:00+01:00)",
+                e
+            )
+        })
+}
+
+/// How the dates of the entries are displayed
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq)]
+pub enum DateFormat {
+    /// The day and the name of the month, such as "24 December"
+    Text,
+    /// ISO 8601, such as "1990-12-24", or "--12-24" if the year is unknown
+    Iso,
+    /// The short name of the month and the ordinal day, such as "Dec 24th"
+    Compact,
+}
+
+/// A format that entries can be exported to
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq)]
+pub enum ExportFormat {
+    /// The same format as the birthday file
+    Toml,
+    /// Comma-separated values, with the columns given by --schema
+    Csv,
+}
+
+/// The columns of an exported CSV file
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq)]
+pub enum CsvSchema {
+    /// The fields of the birthday file: name, day, month, year and timezone
+    Generic,
+    /// The "Name" and "Birthday" columns that Google Contacts imports,
+    /// with the birthday as YYYY-MM-DD, or --MM-DD if the year is unknown
+    Google,
+}
+
+/// A column of the table displayed by the list command
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq)]
+pub enum Column {
+    Index,
+    Name,
+    Date,
+    Age,
+    In,
+    Budget,
+    Stone,
+    /// The day of the week the person was born on
+    Born,
+    /// The date of birth in the calendar of --calendar-display
+    Calendar,
+    /// The timezone stored for the entry
+    Timezone,
+}
+
+impl Column {
+    /// The header of the column
+    pub fn title(&self) -> &'static str {
+        match self {
+            Column::Index => "#",
+            Column::Name => "Name",
+            Column::Date => "Date",
+            Column::Age => "Age",
+            Column::In => "In",
+            Column::Budget => "Budget",
+            Column::Stone => "Stone",
+            Column::Born => "Born",
+            Column::Calendar => "Calendar",
+            Column::Timezone => "Timezone",
+        }
+    }
+}
+
+/// How the list command can group entries
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq)]
+pub enum GroupBy {
+    /// Group entries by their relationship
+    Relationship,
+    /// Group entries by the season of their birthday, from the hemisphere set in the birthday file
+    Season,
+}
+
+/// What the list command sorts entries by
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq)]
+pub enum SortBy {
+    /// The date of the next birthday (or the day of the month with --month)
+    Date,
+    /// The age of the person
+    Age,
+    /// The number of days until the next birthday, counted in the timezone of each entry,
+    /// so a birthday that already started there comes before one that starts sooner here
+    Days,
+    /// The last name of the person when it is known, otherwise the name, in alphabetical order
+    Name,
+    /// The month and the day, from january to december whatever the current date
+    Month,
+}
+
+/// The direction the list command sorts entries in
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq)]
+pub enum SortDir {
+    /// The closest birthday, the youngest person or the first name in alphabetical order first
+    Asc,
+    /// The farthest birthday, the oldest person or the last name in alphabetical order first
+    Desc,
+}
+
+/// Where entries with an unknown age are displayed
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq)]
+pub enum UnknownAge {
+    First,
+    Last,
+    /// Don't display them
+    Hidden,
+}
+
+impl Commands {
+    /// Whether the command modifies the birthday file
+    pub fn is_mutating(&self) -> bool {
+        matches!(
+            self,
+            Commands::Add { .. }
+                | Commands::Rename { .. }
+                | Commands::Fmt
+                | Commands::Prune { .. }
+                | Commands::Undo
+                | Commands::Import { check: false, .. }
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{json_errors_requested, parse_limit, parse_now, parse_separator};
+    use test_case::test_case;
+
+    #[test_case("1", Ok(1) ; "One")]
+    #[test_case("20", Ok(20) ; "Several")]
+    #[test_case("0", Err(()) ; "Zero")]
+    #[test_case("-1", Err(()) ; "Negative")]
+    fn test_parse_limit(s: &str, expected: Result<usize, ()>) {
+        assert_eq!(parse_limit(s).map_err(|_| ()), expected);
+    }
+
+    #[test_case(&["bday", "--error-format", "json", "lis"], true ; "Separate value")]
+    #[test_case(&["bday", "lis", "--error-format=json"], true ; "Value after an equal sign")]
+    #[test_case(&["bday", "--error-format", "text", "lis"], false ; "Text format")]
+    #[test_case(&["bday", "lis", "json"], false ; "No option")]
+    fn test_json_errors_requested(args: &[&str], expected: bool) {
+        assert_eq!(
+            json_errors_requested(args.iter().map(|arg| arg.to_string())),
+            expected
+        );
+    }
+
+    #[test_case(",", Ok(b',') ; "Comma")]
+    #[test_case(";", Ok(b';') ; "Semicolon")]
+    #[test_case("\\t", Ok(b'\t') ; "Escaped tab")]
+    #[test_case("", Err(()) ; "Empty")]
+    #[test_case(";;", Err(()) ; "Several characters")]
+    #[test_case("§", Err(()) ; "Not ASCII")]
+    fn test_parse_separator(s: &str, expected: Result<u8, ()>) {
+        assert_eq!(parse_separator(s).map_err(|_| ()), expected);
+    }
+
+    #[test_case("2024-12-24T09:00:00+01:00", Some("2024-12-24T08:00:00Z") ; "With an offset")]
+    #[test_case("2024-12-24T09:00:00Z", Some("2024-12-24T09:00:00Z") ; "In UTC")]
+    #[test_case("2024-12-24T09:00:00", None ; "Without an offset")]
+    #[test_case("2024-12-24", None ; "Without a time")]
+    fn test_parse_now(s: &str, expected: Option<&str>) {
+        assert_eq!(
+            parse_now(s)
+                .ok()
+                .map(|now| now.to_rfc3339_opts(chrono::SecondsFormat::Secs, true)),
+            expected.map(str::to_string)
+        );
+    }
 }
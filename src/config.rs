@@ -1,13 +1,35 @@
 use crate::utils;
-use chrono::{DateTime, Datelike, Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
-use chrono_tz::{ParseError, Tz};
+use chrono::{
+    DateTime, Datelike, FixedOffset, Local, Months, NaiveDate, NaiveDateTime, NaiveTime, TimeDelta,
+    TimeZone, Weekday,
+};
+use chrono_tz::Tz;
 use clap::error::Result;
+use clap::ValueEnum;
 use serde::{Deserialize, Serialize};
-use std::path::{self, PathBuf};
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::ops::RangeInclusive;
+use std::path::{self, Path, PathBuf};
 use std::str::FromStr;
-use std::{fmt, fs};
+use std::time::Duration;
+use std::{fmt, fs, io, thread};
+use toml_edit::{ArrayOfTables, Document, Item, Key, Table, TableLike, Value};
 
-#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+/// The years that dates can have, so that ages stay meaningful and the dates computed from them are valid
+const VALID_YEARS: RangeInclusive<i32> = 1..=9999;
+
+/// Check that a year, if there is one, is in VALID_YEARS
+fn check_year(year: Option<i32>) -> Result<(), &'static str> {
+    match year {
+        Some(year) if !VALID_YEARS.contains(&year) => {
+            Err("Invalid year, it must be between 1 and 9999")
+        }
+        _ => Ok(()),
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy, Serialize)]
 pub struct BirthdayDate {
     pub day: u32,
     pub month: u32,
@@ -15,11 +37,81 @@ pub struct BirthdayDate {
 }
 
 impl BirthdayDate {
+    /// Create a BirthdayDate, checking that the date exists
+    pub fn new(day: u32, month: u32, year: Option<i32>) -> Result<Self, &'static str> {
+        check_year(year)?;
+        // We use 2000 as default as it is a leap year, so it can handle february 29th
+        if NaiveDate::from_ymd_opt(year.unwrap_or(2000), month, day).is_none() {
+            return Err("Invalid date");
+        }
+        Ok(Self { day, month, year })
+    }
+
     /// Get the date as a NaiveDate, using 2000 as default year if the year is not provided.  
     /// This is useful if you need a NaiveDate but don't care about the year.
     pub fn naive_date_safe_year(&self) -> NaiveDate {
         NaiveDate::from_ymd_opt(self.year.unwrap_or(2000), self.month, self.day).unwrap()
     }
+
+    /// A key to sort dates in calendar order regardless of the year, the month * 100 + the day
+    /// (so december 24th is 1224, and february 29th comes between the 28th and march 1st)
+    pub fn month_day_ordinal(&self) -> u32 {
+        self.month * 100 + self.day
+    }
+
+    /// Whether the year is after the year of the given date, which is likely a typo for a birth date
+    pub fn has_future_year(&self, today: NaiveDate) -> bool {
+        self.year.is_some_and(|year| year > today.year())
+    }
+
+    /// The day of the week the person was born on, or None if the year is not provided.
+    pub fn birth_weekday(&self) -> Option<Weekday> {
+        self.year
+            .and_then(|year| NaiveDate::from_ymd_opt(year, self.month, self.day))
+            .map(|date| date.weekday())
+    }
+
+    /// Get the number of full months the person has lived on the given date, or None if the year is not provided.
+    pub fn age_in_months_on(&self, date: NaiveDate) -> Option<i32> {
+        self.year.map(|year| {
+            let months = (date.year() - year) * 12 + date.month() as i32 - self.month as i32;
+            if date.day() < self.day {
+                months - 1
+            } else {
+                months
+            }
+        })
+    }
+
+    /// Get the date 6 months after this one, clamped to the end of the month
+    /// (so august 31st becomes february 28th, or 29th on leap years).
+    /// Returns None if the date doesn't exist.
+    pub fn half_birthday(&self) -> Option<BirthdayDate> {
+        // Without a year, the default one is a leap year for the first half of the year (so february 29th exists),
+        // and the year before a leap year for the second half (so the half birthday can fall on february 29th)
+        let default_year = if self.month <= 6 { 2000 } else { 1999 };
+        let date =
+            NaiveDate::from_ymd_opt(self.year.unwrap_or(default_year), self.month, self.day)?
+                .checked_add_months(Months::new(6))?;
+        Some(BirthdayDate {
+            day: date.day(),
+            month: date.month(),
+            year: self.year.map(|_| date.year()),
+        })
+    }
+
+    /// Get the age of the person on the given date, or None if the year is not provided.
+    /// On non-leap years, people born on february 29th get older on the day given by leap_day.
+    pub fn age_on(&self, date: NaiveDate, leap_day: LeapDay) -> Option<i32> {
+        self.year.map(|year| {
+            let birthday = utils::make_date_safe(date.year(), self.month, self.day, leap_day);
+            if date < birthday {
+                date.year() - year - 1
+            } else {
+                date.year() - year
+            }
+        })
+    }
 }
 
 impl From<NaiveDate> for BirthdayDate {
@@ -42,16 +134,12 @@ impl FromStr for BirthdayDate {
 
         // Determine positions of day, month, and year based on the format
         let (day, month, year) = match date_parts.len() {
-            2 => {
-                // DD/MM format
-                if separator == '/' {
-                    let day = date_parts[0].parse().map_err(|_| "Invalid day")?;
-                    let month = date_parts[1].parse().map_err(|_| "Invalid month")?;
-                    let year = None;
-                    (day, month, year)
-                } else {
-                    return Err("Invalid date format, use DD/MM, DD/MM/YYYY, or YYYY-MM-DD");
-                }
+            // DD/MM format
+            2 if separator == '/' => {
+                let day = date_parts[0].parse().map_err(|_| "Invalid day")?;
+                let month = date_parts[1].parse().map_err(|_| "Invalid month")?;
+                let year = None;
+                (day, month, year)
             }
             3 => {
                 // YYYY-MM-DD format
@@ -72,13 +160,7 @@ impl FromStr for BirthdayDate {
             _ => return Err("Invalid date format, use DD/MM, DD/MM/YYYY, or YYYY-MM-DD"),
         };
 
-        // Check if the date is valid
-        // We use 2020 as default as it is a leap year, so it can handle february 29th
-        if NaiveDate::from_ymd_opt(year.unwrap_or(2000), month, day).is_none() {
-            return Err("Invalid date");
-        }
-
-        Ok(Self { day, month, year })
+        Self::new(day, month, year)
     }
 }
 
@@ -91,48 +173,466 @@ impl fmt::Display for BirthdayDate {
     }
 }
 
-#[derive(Deserialize, Debug, Serialize)]
+/// A date that doesn't fall on the same day every year, but on the n-th weekday of a month
+/// (e.g. the second sunday of may).
+#[derive(Debug, PartialEq, Clone, Copy, Serialize)]
+pub struct WeekdayDate {
+    /// From 1 to 5, 5 meaning the last one of the month
+    pub week_of_month: u8,
+    pub weekday: Weekday,
+    pub month: u32,
+}
+
+/// A date of which only the month (and maybe the year) is known
+#[derive(Debug, PartialEq, Clone, Copy, Serialize)]
+pub struct MonthDate {
+    pub month: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub year: Option<i32>,
+}
+
+impl MonthDate {
+    /// Create a MonthDate, checking that the month and the year are valid
+    pub fn new(month: u32, year: Option<i32>) -> Result<Self, &'static str> {
+        check_year(year)?;
+        if !(1..=12).contains(&month) {
+            return Err("Invalid month");
+        }
+        Ok(Self { month, year })
+    }
+}
+
+/// The date of an entry, as it is stored in the birthday file
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+#[serde(untagged, try_from = "RawEntryDate")]
+pub enum EntryDate {
+    Date(BirthdayDate),
+    Weekday(WeekdayDate),
+    Month(MonthDate),
+}
+
+/// The fields of an EntryDate in the birthday file, before checking that they make a valid date
+#[derive(Deserialize)]
+struct RawEntryDate {
+    day: Option<u32>,
+    month: u32,
+    year: Option<i32>,
+    week_of_month: Option<u8>,
+    weekday: Option<Weekday>,
+}
+
+impl TryFrom<RawEntryDate> for EntryDate {
+    type Error = String;
+
+    fn try_from(raw: RawEntryDate) -> Result<Self, Self::Error> {
+        if let Err(e) = check_year(raw.year) {
+            return Err(e.to_lowercase());
+        }
+        match (raw.day, raw.week_of_month, raw.weekday) {
+            (Some(day), None, None) => BirthdayDate::new(day, raw.month, raw.year)
+                .map(EntryDate::Date)
+                .map_err(|_| format!("invalid date {:02}/{:02}", day, raw.month)),
+            (None, Some(week_of_month), Some(weekday)) => {
+                if !(1..=5).contains(&week_of_month) {
+                    return Err("week_of_month must be between 1 and 5".to_string());
+                }
+                if !(1..=12).contains(&raw.month) {
+                    return Err("month must be between 1 and 12".to_string());
+                }
+                if raw.year.is_some() {
+                    return Err("year can't be used with week_of_month and weekday".to_string());
+                }
+                Ok(EntryDate::Weekday(WeekdayDate {
+                    week_of_month,
+                    weekday,
+                    month: raw.month,
+                }))
+            }
+            (None, None, None) => MonthDate::new(raw.month, raw.year)
+                .map(EntryDate::Month)
+                .map_err(|_| "month must be between 1 and 12".to_string()),
+            _ => Err(
+                "an entry needs either a day, a week_of_month and a weekday, or only a month"
+                    .to_string(),
+            ),
+        }
+    }
+}
+
+impl EntryDate {
+    /// The year of birth, if it is known
+    pub fn year(&self) -> Option<i32> {
+        match self {
+            EntryDate::Date(date) => date.year,
+            EntryDate::Weekday(_) => None,
+            EntryDate::Month(date) => date.year,
+        }
+    }
+
+    /// Whether the year is after the year of the given date, see BirthdayDate::has_future_year
+    pub fn has_future_year(&self, today: NaiveDate) -> bool {
+        self.year().is_some_and(|year| year > today.year())
+    }
+
+    /// The date 6 months after this one, see BirthdayDate::half_birthday.
+    /// Weekday dates keep their week and weekday (so the second sunday of may becomes the second sunday of november),
+    /// and dates without a day only change month.
+    pub fn half_birthday(&self) -> Option<EntryDate> {
+        // Like the dates with a day, the year is the one of the half birthday
+        let half_month = |month: u32, year: Option<i32>| match month {
+            1..=6 => (month + 6, year),
+            _ => (month - 6, year.map(|year| year + 1)),
+        };
+        match self {
+            EntryDate::Date(date) => date.half_birthday().map(EntryDate::Date),
+            EntryDate::Weekday(date) => Some(EntryDate::Weekday(WeekdayDate {
+                month: half_month(date.month, None).0,
+                ..*date
+            })),
+            EntryDate::Month(date) => {
+                let (month, year) = half_month(date.month, date.year);
+                Some(EntryDate::Month(MonthDate { month, year }))
+            }
+        }
+    }
+
+    /// The key to sort entries in calendar order, in the same form as BirthdayDate::month_day_ordinal,
+    /// using an approximate day for weekday dates.
+    /// Dates without a day come before the other ones of their month.
+    pub fn month_day_ordinal(&self) -> u32 {
+        match self {
+            EntryDate::Date(date) => date.month_day_ordinal(),
+            EntryDate::Month(date) => date.month * 100,
+            EntryDate::Weekday(date) => {
+                date.month * 100
+                    + (date.week_of_month as u32 - 1) * 7
+                    + date.weekday.number_from_monday()
+            }
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Serialize)]
 pub struct ConfigEntry {
     pub name: String,
     #[serde(flatten)]
-    pub date: BirthdayDate,
+    pub date: EntryDate,
     pub timezone: Option<String>,
+    /// Hidden entries are not listed unless explicitly asked for
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub hidden: bool,
+    /// Other names the entry can be found by
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub aliases: Vec<String>,
+    /// The first name, used with the last name to display the name as "Last, First"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub first_name: Option<String>,
+    /// The last name, used to display the name as "Last, First" and to sort entries by last name
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_name: Option<String>,
+    /// How much to spend on a gift, free-form (e.g. "50€")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub budget: Option<String>,
+    /// A picture of the person, for front-ends that display one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub photo: Option<PathBuf>,
+    /// The email address of the person, to send them a birthday message
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+    /// Used to pick the greeting message, for example "mom" or "friend"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub relationship: Option<String>,
+    /// Whether the date is a birthday or the anniversary of an event, such as a wedding
+    #[serde(default, skip_serializing_if = "EntryKind::is_birthday")]
+    pub kind: EntryKind,
+    /// The time of the day at which the birthday starts, midnight if None
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time: Option<NaiveTime>,
+    /// Where the entry comes from, for example the file it was imported from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+}
+
+/// What the date of an entry celebrates, which only changes how the years since it are described
+#[derive(Debug, Default, PartialEq, Clone, Copy, Deserialize, Serialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum EntryKind {
+    /// The person gets a year older, "turning 25"
+    #[default]
+    Birthday,
+    /// The years since an event are counted, "25 years"
+    Anniversary,
+}
+
+impl EntryKind {
+    fn is_birthday(&self) -> bool {
+        *self == EntryKind::Birthday
+    }
+
+    /// The name of the occurences of the date, such as in "Next birthday"
+    pub fn occurence_name(&self) -> &'static str {
+        match self {
+            EntryKind::Birthday => "birthday",
+            EntryKind::Anniversary => "anniversary",
+        }
+    }
+}
+
+impl ConfigEntry {
+    /// Whether the entries have the same name (ignoring case and accents) and date
+    pub fn is_duplicate_of(&self, other: &ConfigEntry) -> bool {
+        utils::names_match(&self.name, &other.name) && self.date == other.date
+    }
+
+    /// Whether the name or one of the aliases of the entry is the given name, ignoring case and accents
+    pub fn has_name(&self, name: &str) -> bool {
+        utils::names_match(&self.name, name)
+            || self
+                .aliases
+                .iter()
+                .any(|alias| utils::names_match(alias, name))
+    }
+
+    /// Whether the name or one of the aliases of the entry contains the given text, ignoring case and accents
+    pub fn has_name_containing(&self, search: &str) -> bool {
+        utils::name_contains(&self.name, search)
+            || self
+                .aliases
+                .iter()
+                .any(|alias| utils::name_contains(alias, search))
+    }
+}
+
+/// The timezone of an entry, either a named timezone or a fixed offset from UTC
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum EntryTimezone {
+    Named(Tz),
+    Fixed(FixedOffset),
+}
+
+impl EntryTimezone {
+    /// Parse a fixed offset such as "+05:30", "-8" or "UTC+5:30"
+    fn parse_offset(s: &str) -> Option<FixedOffset> {
+        let s = s.trim();
+        let s = match s.get(..3) {
+            Some(prefix)
+                if prefix.eq_ignore_ascii_case("utc") || prefix.eq_ignore_ascii_case("gmt") =>
+            {
+                &s[3..]
+            }
+            _ => s,
+        };
+        let sign = match s.chars().next()? {
+            '+' => 1,
+            '-' => -1,
+            _ => return None,
+        };
+        let (hours, minutes) = s[1..].split_once(':').unwrap_or((&s[1..], "0"));
+        let hours: i32 = hours.parse().ok()?;
+        let minutes: i32 = minutes.parse().ok()?;
+        if !(0..60).contains(&minutes) {
+            return None;
+        }
+        FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60))
+    }
+}
+
+impl FromStr for EntryTimezone {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Ok(tz) = Tz::from_str_insensitive(s) {
+            return Ok(EntryTimezone::Named(tz));
+        }
+        Self::parse_offset(s)
+            .map(EntryTimezone::Fixed)
+            .ok_or_else(|| format!("'{}' is not a valid timezone or UTC offset", s))
+    }
+}
+
+impl fmt::Display for EntryTimezone {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EntryTimezone::Named(tz) => write!(f, "{}", tz.name()),
+            EntryTimezone::Fixed(offset) => write!(f, "{}", offset),
+        }
+    }
 }
 
 pub struct Entry {
     pub name: String,
+    pub aliases: Vec<String>,
+    pub first_name: Option<String>,
+    pub last_name: Option<String>,
+    pub budget: Option<String>,
+    pub photo: Option<PathBuf>,
+    pub email: Option<String>,
+    pub relationship: Option<String>,
+    pub kind: EntryKind,
+    /// For weekday dates, this is the date of the closest occurence, without a year.
+    /// For dates without a day, this is the first day of the month.
     pub date: BirthdayDate,
+    /// Whether only the month of the date is known, in which case the occurences are the first day of the month
+    pub day_unknown: bool,
     /// Considered as the local timezone if None
-    pub timezone: Option<Tz>,
+    pub timezone: Option<EntryTimezone>,
+    /// Considered as midnight if None
+    pub time: Option<NaiveTime>,
+    pub source: Option<String>,
+    /// When the birthday is celebrated on non-leap years, if the date is february 29th
+    pub leap_day: LeapDay,
+    /// The date the occurences and age are computed from,
+    /// which is the current date in the timezone of the entry unless another date is given.
+    pub today: NaiveDate,
     /// The previous occurence of the date from today.
     /// If the date is today, this will be None.
     /// The time correspond be 23h59 in the requested timezone (aka the end of the date).
     pub prev_occurence: Option<DateTime<Local>>,
     /// The next occurence of the date from today.
     /// If the date is today, this will be None.
-    /// The time correspond to midnight in the requested timezone (aka the begining of the date).
+    /// The time correspond to midnight (or the time of the entry) in the requested timezone (aka the begining of the date).
     pub next_occurence: Option<DateTime<Local>>,
 }
 
+#[derive(Debug)]
 pub enum EntryError {
-    TimezoneParseError(ParseError),
+    TimezoneParseError(String),
 }
 
-/// Convert a naive DateTime (that is in the specified timezone) to the local timezone.
+/// Get the date it is at the given time in the specified timezone.
 /// If no timezone is provided, the timezone used is the local timezone.
-fn localize_naive_datetime(dt: NaiveDateTime, timezone: Option<Tz>) -> DateTime<Local> {
+fn date_in_timezone<Tz: TimeZone>(dt: &DateTime<Tz>, timezone: Option<EntryTimezone>) -> NaiveDate {
     match timezone {
-        Some(tz) => tz.from_local_datetime(&dt).unwrap().with_timezone(&Local),
-        None => Local.from_local_datetime(&dt).unwrap(),
+        Some(EntryTimezone::Named(tz)) => dt.with_timezone(&tz).date_naive(),
+        Some(EntryTimezone::Fixed(offset)) => dt.with_timezone(&offset).date_naive(),
+        None => dt.with_timezone(&Local).date_naive(),
     }
 }
 
-impl TryFrom<ConfigEntry> for Entry {
-    type Error = EntryError;
+impl Entry {
+    /// The name as "Last, First" if the last name is known, otherwise the name
+    pub fn reversed_name(&self) -> String {
+        match (&self.first_name, &self.last_name) {
+            (Some(first_name), Some(last_name)) => format!("{}, {}", last_name, first_name),
+            (None, Some(last_name)) => last_name.clone(),
+            (_, None) => self.name.clone(),
+        }
+    }
 
-    fn try_from(config_entry: ConfigEntry) -> Result<Self, EntryError> {
-        let timezone: Option<Tz> = match config_entry.timezone {
-            Some(tz) => match Tz::from_str_insensitive(&tz) {
+    /// The current age of the person, or None if the year of birth is unknown
+    pub fn age_now(&self) -> Option<i32> {
+        self.date.age_on(self.today, self.leap_day)
+    }
+
+    /// The current age of the person in months, or None if the year of birth is unknown
+    pub fn age_in_months_now(&self) -> Option<i32> {
+        self.date.age_in_months_on(self.today)
+    }
+
+    /// The number of days until the next birthday, or 0 if it is today
+    /// (or, for dates without a day, if it is during the month of the birthday).
+    /// The days are counted in the timezone of the entry, so a birthday can be a day closer
+    /// (or farther) than it would be in the local timezone.
+    pub fn num_days_until(&self) -> i64 {
+        match self.next_occurence {
+            Some(next) => (date_in_timezone(&next, self.timezone) - self.today)
+                .num_days()
+                .max(0),
+            None => 0,
+        }
+    }
+
+    /// The date of the next birthday in the timezone of the entry, today if it is today.
+    /// On non-leap years, this is the day given by leap_day for february 29th.
+    pub fn next_date(&self) -> NaiveDate {
+        self.today + TimeDelta::days(self.num_days_until())
+    }
+
+    /// The age the person will turn on their next birthday (or turned today),
+    /// or None if the year of birth is unknown
+    pub fn next_age(&self) -> Option<i32> {
+        if self.day_unknown {
+            // The age changes at the start of the month, so it already changed during the month of the birthday
+            return self.date.age_on(
+                self.today + TimeDelta::days(self.num_days_until()),
+                self.leap_day,
+            );
+        }
+        self.age_now().map(|age| match self.next_occurence {
+            Some(_) => age + 1,
+            // The birthday is today
+            None => age,
+        })
+    }
+}
+
+/// Build an entry from an entry of the birthday file written in TOML, computing its occurences from the given date
+#[cfg(test)]
+pub fn test_entry(toml_str: &str, today: Option<NaiveDate>) -> Entry {
+    let config_entry: ConfigEntry = toml::from_str(toml_str).unwrap();
+    Entry::from_config(config_entry, today, LeapDay::Feb28).unwrap()
+}
+
+/// Sort entries by date of next occurence, from the farthest to the closest.
+/// Entries happening at the same time are sorted by name, so the order is always the same.
+pub fn sort_entries(entries: &mut [Entry]) {
+    entries.sort_by_cached_key(|entry| {
+        (
+            Reverse(entry.next_occurence),
+            utils::collation_key(&entry.name),
+            entry.name.clone(),
+        )
+    });
+}
+
+/// Sort the entries of a single month by day, from the first to the last day of the month (or the opposite).
+/// Entries of the same day are sorted by name, even if their timezones make them start at different times.
+pub fn sort_entries_by_day(entries: &mut [&Entry], descending: bool) {
+    entries.sort_by_cached_key(|entry| (utils::collation_key(&entry.name), entry.name.clone()));
+    // The sort is stable, so entries of the same day stay sorted by name
+    if descending {
+        entries.sort_by_key(|entry| Reverse(entry.date.day));
+    } else {
+        entries.sort_by_key(|entry| entry.date.day);
+    }
+}
+
+/// Sort entries in calendar order, from january to december (or the opposite), whatever the current date.
+/// Entries without a day come before the other entries of their month, and entries of the same day are sorted by name.
+pub fn sort_entries_by_calendar(entries: &mut [&Entry], descending: bool) {
+    entries.sort_by_cached_key(|entry| (utils::collation_key(&entry.name), entry.name.clone()));
+    // Same key as EntryDate::month_day_ordinal, where the date of entries without a day is the first of the month
+    let ordinal = |entry: &&Entry| match entry.day_unknown {
+        true => entry.date.month * 100,
+        false => entry.date.month_day_ordinal(),
+    };
+    // The sort is stable, so entries of the same day stay sorted by name
+    if descending {
+        entries.sort_by_key(|entry| Reverse(ordinal(entry)));
+    } else {
+        entries.sort_by_key(ordinal);
+    }
+}
+
+/// Convert a naive DateTime (that is in the specified timezone) to the local timezone.
+/// If no timezone is provided, the timezone used is the local timezone.
+fn localize_naive_datetime(dt: NaiveDateTime, timezone: Option<EntryTimezone>) -> DateTime<Local> {
+    match timezone {
+        Some(EntryTimezone::Named(tz)) => utils::localize(&tz, dt).with_timezone(&Local),
+        Some(EntryTimezone::Fixed(offset)) => utils::localize(&offset, dt).with_timezone(&Local),
+        None => utils::localize(&Local, dt),
+    }
+}
+
+impl Entry {
+    /// Parse a ConfigEntry, computing its occurences from the given date instead of the current one if there is one
+    pub fn from_config(
+        config_entry: ConfigEntry,
+        today: Option<NaiveDate>,
+        leap_day: LeapDay,
+    ) -> Result<Self, EntryError> {
+        let timezone: Option<EntryTimezone> = match config_entry.timezone {
+            Some(tz) => match tz.parse() {
                 Ok(parsed_tz) => Some(parsed_tz),
                 Err(e) => Err(EntryError::TimezoneParseError(e))?,
             },
@@ -150,24 +650,49 @@ impl TryFrom<ConfigEntry> for Entry {
         // };
 
         // The current date in the timezone of the entry
-        let date_tz: NaiveDate = match timezone {
-            Some(tz) => tz.from_utc_datetime(&Utc::now().naive_utc()).date_naive(),
-            None => Local::now().naive_local().date(),
-        };
+        let date_tz: NaiveDate = today.unwrap_or_else(|| date_in_timezone(&utils::now(), timezone));
 
         // We call it with the current time it is in the timezone of the entry
-        let (prev_occurence, next_occurence) = match utils::find_prev_next_occurences(
-            config_entry.date.day,
-            config_entry.date.month,
-            date_tz,
-        ) {
+        let occurences = match config_entry.date {
+            EntryDate::Date(date) => {
+                utils::find_prev_next_occurences(date.day, date.month, date_tz, leap_day)
+            }
+            EntryDate::Weekday(date) => utils::find_prev_next_weekday_occurences(
+                date.week_of_month,
+                date.weekday,
+                date.month,
+                date_tz,
+            ),
+            EntryDate::Month(date) => {
+                Some(utils::find_prev_next_month_occurences(date.month, date_tz))
+            }
+        };
+
+        let date = match config_entry.date {
+            EntryDate::Date(date) => date,
+            EntryDate::Weekday(_) => {
+                let closest = occurences.map(|(_, next)| next).unwrap_or(date_tz);
+                BirthdayDate {
+                    day: closest.day(),
+                    month: closest.month(),
+                    year: None,
+                }
+            }
+            EntryDate::Month(date) => BirthdayDate {
+                day: 1,
+                month: date.month,
+                year: date.year,
+            },
+        };
+
+        let (prev_occurence, next_occurence) = match occurences {
             Some((prev, next)) => (
                 Some(localize_naive_datetime(
                     prev.and_hms_opt(23, 59, 59).unwrap(),
                     timezone,
                 )),
                 Some(localize_naive_datetime(
-                    next.and_hms_opt(0, 0, 0).unwrap(),
+                    next.and_time(config_entry.time.unwrap_or(NaiveTime::MIN)),
                     timezone,
                 )),
             ),
@@ -176,49 +701,1147 @@ impl TryFrom<ConfigEntry> for Entry {
 
         Ok(Self {
             name: config_entry.name,
-            date: config_entry.date,
+            aliases: config_entry.aliases,
+            first_name: config_entry.first_name,
+            last_name: config_entry.last_name,
+            budget: config_entry.budget,
+            photo: config_entry.photo,
+            email: config_entry.email,
+            relationship: config_entry.relationship,
+            kind: config_entry.kind,
+            date,
+            day_unknown: matches!(config_entry.date, EntryDate::Month(_)),
             timezone,
+            time: config_entry.time,
+            source: config_entry.source,
+            leap_day,
+            today: date_tz,
             prev_occurence,
             next_occurence,
         })
     }
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+impl TryFrom<ConfigEntry> for Entry {
+    type Error = EntryError;
+
+    fn try_from(config_entry: ConfigEntry) -> Result<Self, EntryError> {
+        Entry::from_config(config_entry, None, LeapDay::default())
+    }
+}
+
+/// How precisely durations are displayed
+#[derive(Debug, Default, PartialEq, Clone, Copy, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Humanize {
+    /// For example "in 3 weeks"
+    #[default]
+    Rough,
+    /// For example "in 3 weeks, 2 days, 4 hours and 12 minutes"
+    Precise,
+}
+
+/// The hemisphere the seasons are those of
+#[derive(Debug, Default, PartialEq, Clone, Copy, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Hemisphere {
+    /// Winter is from December to February
+    #[default]
+    Northern,
+    /// Winter is from June to August
+    Southern,
+}
+
+/// The borders of the tables, named after the formats of prettytable
+#[derive(Debug, Default, PartialEq, Clone, Copy, Deserialize, Serialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum TableStyle {
+    /// Lines with rounded corners
+    #[default]
+    Rounded,
+    /// Lines with square corners
+    Box,
+    /// Lines made of ASCII characters
+    Ascii,
+    /// ASCII lines, without lines between the rows
+    Compact,
+    /// Only spaces between the columns
+    Clean,
+    /// Lines between the rows and columns, without an outer border
+    Borderless,
+}
+
+#[derive(Debug, Default, PartialEq, Clone, Deserialize, Serialize)]
+pub struct DisplayConfig {
+    #[serde(default)]
+    pub humanize: Humanize,
+    #[serde(default)]
+    pub hemisphere: Hemisphere,
+    #[serde(default)]
+    pub table_style: TableStyle,
+}
+
+impl DisplayConfig {
+    fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Clone, Deserialize, Serialize)]
+pub struct MetaConfig {
+    /// Refuse to modify the birthday file
+    #[serde(default)]
+    pub readonly: bool,
+}
+
+impl MetaConfig {
+    fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// When birthdays on february 29th are celebrated on non-leap years
+#[derive(Debug, Default, PartialEq, Clone, Copy, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LeapDay {
+    /// The day before, february 28th
+    #[default]
+    Feb28,
+    /// The day after, march 1st
+    Mar01,
+}
+
+#[derive(Debug, Default, PartialEq, Clone, Deserialize, Serialize)]
+pub struct OptionsConfig {
+    #[serde(default)]
+    pub leap_day: LeapDay,
+    /// The ages highlighted by list --milestones, all the multiples of 10 if empty
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub milestones: Vec<i32>,
+}
+
+impl OptionsConfig {
+    fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Whether turning the given age is a milestone
+    pub fn is_milestone(&self, age: i32) -> bool {
+        match self.milestones.is_empty() {
+            true => age > 0 && age % 10 == 0,
+            false => self.milestones.contains(&age),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Deserialize, Serialize)]
+pub struct ImportConfig {
+    /// Importing more entries than this asks for confirmation, to avoid importing a file twice by mistake
+    #[serde(default = "ImportConfig::default_confirm_above")]
+    pub confirm_above: usize,
+}
+
+impl ImportConfig {
+    fn default_confirm_above() -> usize {
+        100
+    }
+
+    fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+impl Default for ImportConfig {
+    fn default() -> Self {
+        Self {
+            confirm_above: Self::default_confirm_above(),
+        }
+    }
+}
+
+/// The version of the format of the birthday file, increased when it changes in a way older versions can't read
+pub const CONFIG_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Config {
+    /// Files from before the format was versioned don't have it, so they are version 0
+    #[serde(default)]
+    pub version: u32,
     pub birthdays: Vec<ConfigEntry>,
+    #[serde(default, skip_serializing_if = "DisplayConfig::is_default")]
+    pub display: DisplayConfig,
+    #[serde(default, skip_serializing_if = "MetaConfig::is_default")]
+    pub meta: MetaConfig,
+    #[serde(default, skip_serializing_if = "ImportConfig::is_default")]
+    pub import: ImportConfig,
+    #[serde(default, skip_serializing_if = "OptionsConfig::is_default")]
+    pub options: OptionsConfig,
+    /// Greeting templates by relationship, where "{name}" is replaced by the name of the entry.
+    /// The "default" template is used for entries without a template for their relationship.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub greetings: BTreeMap<String, String>,
 }
 
 impl Default for Config {
     fn default() -> Self {
-        Self { birthdays: vec![] }
+        Self {
+            version: CONFIG_VERSION,
+            birthdays: vec![],
+            display: DisplayConfig::default(),
+            meta: MetaConfig::default(),
+            import: ImportConfig::default(),
+            options: OptionsConfig::default(),
+            greetings: BTreeMap::new(),
+        }
+    }
+}
+
+/// The greeting used if there is no template for the relationship, nor a default one
+const DEFAULT_GREETING: &str = "Happy birthday, {name}!";
+
+impl Config {
+    /// Get the greeting message for an entry, using the template for its relationship
+    pub fn greeting(&self, entry: &Entry) -> String {
+        entry
+            .relationship
+            .as_ref()
+            .and_then(|relationship| self.greetings.get(relationship))
+            .or(self.greetings.get("default"))
+            .map(String::as_str)
+            .unwrap_or(DEFAULT_GREETING)
+            .replace("{name}", &entry.name)
+    }
+}
+
+impl Config {
+    /// Put the config in a canonical form: entries sorted by month, day then name,
+    /// and timezones using their canonical name.
+    /// Returns the previous position of each entry, in their new order.
+    pub fn normalize(&mut self) -> Vec<usize> {
+        for entry in self.birthdays.iter_mut() {
+            if let Some(Ok(tz)) = entry.timezone.as_deref().map(EntryTimezone::from_str) {
+                entry.timezone = Some(tz.to_string());
+            }
+        }
+        let mut order: Vec<usize> = (0..self.birthdays.len()).collect();
+        order.sort_by(|a, b| {
+            let (a, b) = (&self.birthdays[*a], &self.birthdays[*b]);
+            a.date
+                .month_day_ordinal()
+                .cmp(&b.date.month_day_ordinal())
+                .then_with(|| utils::collation_key(&a.name).cmp(&utils::collation_key(&b.name)))
+                .then_with(|| a.name.cmp(&b.name))
+        });
+        self.birthdays = order
+            .iter()
+            .map(|position| self.birthdays[*position].clone())
+            .collect();
+        order
     }
 }
 
 pub struct ConfigFile {
     pub path: path::PathBuf,
     pub config: Config,
+    /// The content of the file as it will be written back, keeping its comments and formatting
+    pub document: Document,
+}
+
+impl ConfigFile {
+    /// A config file that doesn't exist yet
+    pub fn new(path: PathBuf) -> Self {
+        let config = Config::default();
+        let mut document = Document::new();
+        document["version"] = toml_edit::value(i64::from(config.version));
+        Self {
+            path,
+            config,
+            document,
+        }
+    }
+
+    /// Add an entry at the end of the file
+    pub fn add_entry(&mut self, entry: ConfigEntry) {
+        let table = toml_edit::ser::to_document(&entry)
+            .expect("Error serializing entry")
+            .as_table()
+            .clone();
+        match self
+            .document
+            .entry("birthdays")
+            .or_insert(Item::ArrayOfTables(ArrayOfTables::new()))
+        {
+            Item::Value(Value::Array(array)) => array.push(table.into_inline_table()),
+            item => {
+                if let Some(tables) = item.as_array_of_tables_mut() {
+                    tables.push(table)
+                }
+            }
+        }
+        self.config.birthdays.push(entry);
+    }
+
+    /// Change the name of the entry at the given position
+    pub fn rename_entry(&mut self, position: usize, name: &str) {
+        if let Some(value) = self.document["birthdays"][position]["name"].as_value_mut() {
+            // Keep the comment that may follow the name
+            let decor = value.decor().clone();
+            *value = Value::from(name);
+            *value.decor_mut() = decor;
+        }
+        self.config.birthdays[position].name = name.to_string();
+    }
+
+    /// Remove the entries at the given positions
+    pub fn remove_entries(&mut self, positions: &[usize]) {
+        let mut positions = positions.to_vec();
+        // Removing from the end so the other positions don't change
+        positions.sort_unstable_by(|a, b| b.cmp(a));
+        for position in positions {
+            match self.document.get_mut("birthdays") {
+                Some(Item::Value(Value::Array(array))) => {
+                    array.remove(position);
+                }
+                Some(Item::ArrayOfTables(tables)) => tables.remove(position),
+                _ => {}
+            }
+            self.config.birthdays.remove(position);
+        }
+    }
+
+    /// Put the file in the canonical form of `Config::normalize`, reordering the entries of the document
+    /// instead of rewriting it so the rest of the file and its comments are kept.
+    /// The comments of an entry move along with it.
+    pub fn normalize(&mut self) {
+        let order = self.config.normalize();
+        match self.document.get_mut("birthdays") {
+            Some(Item::ArrayOfTables(tables)) => {
+                // Tables are written in the order of their position in the file, so the entries
+                // take the position of the one they replace
+                let positions: Vec<Option<usize>> = tables.iter().map(Table::position).collect();
+                let mut sorted = ArrayOfTables::new();
+                for (index, previous) in order.iter().enumerate() {
+                    let mut table = tables.get(*previous).unwrap().clone();
+                    normalize_entry_table(&mut table, &self.config.birthdays[index]);
+                    if let Some(position) = positions[index] {
+                        table.set_position(position);
+                    }
+                    sorted.push(table);
+                }
+                *tables = sorted;
+            }
+            Some(Item::Value(Value::Array(array))) => {
+                let mut values: Vec<Value> = array.iter().cloned().collect();
+                for (index, previous) in order.iter().enumerate() {
+                    let mut value = array.get(*previous).unwrap().clone();
+                    if let Some(table) = value.as_inline_table_mut() {
+                        normalize_entry_table(table, &self.config.birthdays[index]);
+                        // Inline tables can't have comments, and their spacing depends on the order of the keys
+                        table.fmt();
+                    }
+                    // The spacing around the entries of an inline array stays in place
+                    *value.decor_mut() = values[index].decor().clone();
+                    values[index] = value;
+                }
+                array.clear();
+                for value in values {
+                    array.push_formatted(value);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Write the keys of an entry of the document in the order and representation of a new entry,
+/// keeping the comments around them. Keys that aren't part of an entry are dropped.
+fn normalize_entry_table(table: &mut dyn TableLike, entry: &ConfigEntry) {
+    let canonical = toml_edit::ser::to_document(entry).expect("Error serializing entry");
+    let previous: Vec<(Key, Item)> = table
+        .iter()
+        .map(|(key, item)| (table.key(key).unwrap().clone(), item.clone()))
+        .collect();
+    table.clear();
+    for (key, item) in canonical.as_table().iter() {
+        let Some((previous_key, previous_item)) = previous
+            .iter()
+            .find(|(previous_key, _)| previous_key.get() == key)
+        else {
+            table.insert(key, item.clone());
+            continue;
+        };
+        let item = match (previous_item.as_value(), item.as_value()) {
+            (Some(previous_value), Some(value))
+                if previous_value.clone().decorated("", "").to_string() != value.to_string() =>
+            {
+                let mut value = value.clone();
+                *value.decor_mut() = previous_value.decor().clone();
+                Item::Value(value)
+            }
+            _ => previous_item.clone(),
+        };
+        table.entry_format(previous_key).or_insert(item);
+    }
 }
 
 pub enum LoadConfigError {
-    /// A config file was found, but there was an error reading it
-    IoError(std::io::Error),
-    /// A config file was found, but there was an error parsing it
-    TomlError(toml::de::Error),
+    /// A config file was found at the given path, but there was an error reading it
+    IoError(PathBuf, std::io::Error),
+    /// A config file was found at the given path, but there was an error parsing it
+    TomlError(PathBuf, toml::de::Error),
     /// No valid config file found
     ConfigNotFound,
+    /// The config file uses a version of the format that is newer than the one supported
+    NewerVersion(u32),
+}
+
+/// Only the version of a config, to check it before parsing the rest of the config
+#[derive(Deserialize)]
+struct ConfigVersion {
+    #[serde(default)]
+    version: u32,
 }
 
-/// Load the given config file
+/// Upgrade the document of a config file to the current version of the format, one version at a time.
+/// The upgraded document is only written back by commands that modify the file.
+fn migrate(document: &mut Document, version: u32) {
+    if version >= CONFIG_VERSION {
+        return;
+    }
+    for version in version..CONFIG_VERSION {
+        match version {
+            // Files from before the format was versioned already have the shape of version 1
+            0 => {}
+            _ => unreachable!("No migration from version {}", version),
+        }
+    }
+    document["version"] = toml_edit::value(i64::from(CONFIG_VERSION));
+}
+
+/// Parse the content of a config file, upgrading it if it uses an older version of the format
+fn parse_config(path: &path::Path, toml_str: &str) -> Result<ConfigFile, LoadConfigError> {
+    let toml_error = |e| LoadConfigError::TomlError(path.to_path_buf(), e);
+    let ConfigVersion { version } = toml::from_str(toml_str).map_err(toml_error)?;
+    if version > CONFIG_VERSION {
+        return Err(LoadConfigError::NewerVersion(version));
+    }
+    let mut document: Document = toml_str
+        .parse()
+        .expect("The birthday file was parsed successfully already");
+    migrate(&mut document, version);
+    let config = toml::from_str(&document.to_string()).map_err(toml_error)?;
+    Ok(ConfigFile {
+        path: path.to_path_buf(),
+        config,
+        document,
+    })
+}
+
+/// Whether the path means that the config should be read from stdin
+pub fn is_stdin(path: &path::Path) -> bool {
+    path == path::Path::new("-")
+}
+
+/// Load the given config file, or read it from stdin if the path is "-"
 pub fn load_config(path: &PathBuf) -> Result<ConfigFile, LoadConfigError> {
-    if path.is_file() {
-        let toml_str = fs::read_to_string(path).map_err(LoadConfigError::IoError)?;
-        return toml::from_str(&toml_str)
-            .map_err(LoadConfigError::TomlError)
-            .map(|config| ConfigFile {
-                path: path.to_path_buf(),
-                config,
-            });
-    }
-    Err(LoadConfigError::ConfigNotFound)
+    let toml_str = if is_stdin(path) {
+        io::read_to_string(io::stdin()).map_err(|e| LoadConfigError::IoError(path.clone(), e))?
+    } else if path.is_file() {
+        fs::read_to_string(path).map_err(|e| LoadConfigError::IoError(path.clone(), e))?
+    } else {
+        return Err(LoadConfigError::ConfigNotFound);
+    };
+    parse_config(path, &toml_str)
+}
+
+pub enum LockConfigError {
+    IoError(io::Error),
+    /// Another process kept the birthday file locked during all the attempts
+    Locked,
+}
+
+/// The path of the backup of the birthday file, an adjacent ".bak" file with its content before the last change
+pub fn backup_path(path: &Path) -> PathBuf {
+    let mut backup_path = path.as_os_str().to_owned();
+    backup_path.push(".bak");
+    PathBuf::from(backup_path)
+}
+
+/// How many times to try locking the birthday file before giving up
+const LOCK_ATTEMPTS: u32 = 10;
+
+/// Lock the birthday file so that no other process modifies it at the same time.
+/// The lock is held on an adjacent ".lock" file, until the returned file is dropped or the process exits.
+pub fn lock_config(path: &Path) -> Result<fs::File, LockConfigError> {
+    let mut lock_path = path.as_os_str().to_owned();
+    lock_path.push(".lock");
+    let lock_file = fs::OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(lock_path)
+        .map_err(LockConfigError::IoError)?;
+
+    for attempt in 1..=LOCK_ATTEMPTS {
+        match lock_file.try_lock() {
+            Ok(()) => return Ok(lock_file),
+            Err(fs::TryLockError::WouldBlock) => {
+                thread::sleep(Duration::from_millis(50 * u64::from(attempt)))
+            }
+            Err(fs::TryLockError::Error(e)) => return Err(LockConfigError::IoError(e)),
+        }
+    }
+    Err(LockConfigError::Locked)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        parse_config, sort_entries, sort_entries_by_calendar, sort_entries_by_day, BirthdayDate,
+        Config, ConfigEntry, ConfigFile, Entry, EntryDate, EntryKind, EntryTimezone, LeapDay,
+        LoadConfigError, MonthDate, OptionsConfig, TableStyle, WeekdayDate, CONFIG_VERSION,
+    };
+    use crate::utils;
+    use chrono::{DateTime, FixedOffset, Local, NaiveDate, NaiveTime, TimeZone, Utc, Weekday};
+    use test_case::test_case;
+
+    #[test_case(NaiveDate::from_ymd_opt(2024, 6, 5).unwrap(), Some(33) ; "Birthday is tomorrow")]
+    #[test_case(NaiveDate::from_ymd_opt(2024, 6, 6).unwrap(), Some(34) ; "Birthday is today")]
+    #[test_case(NaiveDate::from_ymd_opt(2024, 6, 7).unwrap(), Some(34) ; "Birthday was yesterday")]
+    #[test_case(NaiveDate::from_ymd_opt(2030, 1, 1).unwrap(), Some(39) ; "Years later, before the birthday")]
+    #[test_case(NaiveDate::from_ymd_opt(2030, 12, 31).unwrap(), Some(40) ; "Years later, after the birthday")]
+    #[test_case(NaiveDate::from_ymd_opt(2000, 6, 5).unwrap(), Some(9) ; "Years before, the day before the birthday")]
+    fn test_age_on(date: NaiveDate, expected: Option<i32>) {
+        let birthday = BirthdayDate {
+            day: 6,
+            month: 6,
+            year: Some(1990),
+        };
+        assert_eq!(birthday.age_on(date, LeapDay::Feb28), expected);
+    }
+
+    #[test_case(NaiveDate::from_ymd_opt(2023, 2, 27).unwrap(), Some(22) ; "Day before, on a non-leap year")]
+    #[test_case(NaiveDate::from_ymd_opt(2023, 2, 28).unwrap(), Some(23) ; "February 28th on a non-leap year")]
+    #[test_case(NaiveDate::from_ymd_opt(2024, 2, 28).unwrap(), Some(23) ; "February 28th on a leap year")]
+    #[test_case(NaiveDate::from_ymd_opt(2024, 2, 29).unwrap(), Some(24) ; "February 29th on a leap year")]
+    fn test_age_on_leap_day(date: NaiveDate, expected: Option<i32>) {
+        let birthday = BirthdayDate {
+            day: 29,
+            month: 2,
+            year: Some(2000),
+        };
+        assert_eq!(birthday.age_on(date, LeapDay::Feb28), expected);
+    }
+
+    #[test_case(24, 12, 1224 ; "December 24th")]
+    #[test_case(1, 1, 101 ; "January 1st")]
+    #[test_case(29, 2, 229 ; "February 29th")]
+    fn test_month_day_ordinal(day: u32, month: u32, expected: u32) {
+        let date = BirthdayDate::new(day, month, None).unwrap();
+        assert_eq!(date.month_day_ordinal(), expected);
+    }
+
+    #[test]
+    fn test_month_day_ordinal_order() {
+        let mut dates = [
+            EntryDate::Date(BirthdayDate::new(1, 3, Some(1990)).unwrap()),
+            EntryDate::Date(BirthdayDate::new(29, 2, Some(2000)).unwrap()),
+            EntryDate::Month(MonthDate {
+                month: 2,
+                year: None,
+            }),
+            EntryDate::Weekday(WeekdayDate {
+                week_of_month: 2,
+                weekday: Weekday::Sun,
+                month: 2,
+            }),
+            EntryDate::Date(BirthdayDate::new(28, 2, None).unwrap()),
+            EntryDate::Date(BirthdayDate::new(31, 12, None).unwrap()),
+        ];
+        dates.sort_by_key(EntryDate::month_day_ordinal);
+        assert_eq!(
+            dates
+                .iter()
+                .map(EntryDate::month_day_ordinal)
+                .collect::<Vec<_>>(),
+            vec![200, 214, 228, 229, 301, 1231]
+        );
+    }
+
+    #[test]
+    fn test_age_on_unknown_year() {
+        let birthday = BirthdayDate {
+            day: 6,
+            month: 6,
+            year: None,
+        };
+        assert_eq!(
+            birthday.age_on(NaiveDate::from_ymd_opt(2024, 6, 6).unwrap(), LeapDay::Feb28),
+            None
+        );
+    }
+
+    #[test_case(BirthdayDate { day: 15, month: 1, year: Some(2000) },
+                BirthdayDate { day: 15, month: 7, year: Some(2000) } ;
+                "Same year")]
+    #[test_case(BirthdayDate { day: 10, month: 10, year: Some(2000) },
+                BirthdayDate { day: 10, month: 4, year: Some(2001) } ;
+                "Next year")]
+    #[test_case(BirthdayDate { day: 31, month: 8, year: Some(1990) },
+                BirthdayDate { day: 28, month: 2, year: Some(1991) } ;
+                "Clamped to the end of the month")]
+    #[test_case(BirthdayDate { day: 31, month: 8, year: None },
+                BirthdayDate { day: 29, month: 2, year: None } ;
+                "Clamped to february 29th when the year is unknown")]
+    #[test_case(BirthdayDate { day: 29, month: 2, year: None },
+                BirthdayDate { day: 29, month: 8, year: None } ;
+                "February 29th when the year is unknown")]
+    #[test_case(BirthdayDate { day: 29, month: 2, year: Some(2000) },
+                BirthdayDate { day: 29, month: 8, year: Some(2000) } ;
+                "February 29th on a leap year")]
+    fn test_half_birthday(date: BirthdayDate, expected: BirthdayDate) {
+        assert_eq!(date.half_birthday(), Some(expected));
+    }
+
+    #[test_case(EntryDate::Weekday(WeekdayDate { week_of_month: 2, weekday: Weekday::Sun, month: 5 }),
+                EntryDate::Weekday(WeekdayDate { week_of_month: 2, weekday: Weekday::Sun, month: 11 }) ;
+                "Weekday date")]
+    #[test_case(EntryDate::Month(MonthDate { month: 3, year: Some(1990) }),
+                EntryDate::Month(MonthDate { month: 9, year: Some(1990) }) ;
+                "Month date")]
+    #[test_case(EntryDate::Month(MonthDate { month: 10, year: Some(1990) }),
+                EntryDate::Month(MonthDate { month: 4, year: Some(1991) }) ;
+                "Month date on the next year")]
+    fn test_entry_date_half_birthday(date: EntryDate, expected: EntryDate) {
+        assert_eq!(date.half_birthday(), Some(expected));
+    }
+
+    #[test]
+    fn test_half_birthday_of_invalid_date() {
+        let date = BirthdayDate {
+            day: 29,
+            month: 2,
+            year: Some(2001),
+        };
+        assert_eq!(date.half_birthday(), None);
+    }
+
+    #[test_case(NaiveDate::from_ymd_opt(2024, 1, 15).unwrap(), Some(0) ; "Born today")]
+    #[test_case(NaiveDate::from_ymd_opt(2024, 2, 14).unwrap(), Some(0) ; "Day before the first month")]
+    #[test_case(NaiveDate::from_ymd_opt(2024, 2, 15).unwrap(), Some(1) ; "First month")]
+    #[test_case(NaiveDate::from_ymd_opt(2025, 7, 20).unwrap(), Some(18) ; "Over a year")]
+    fn test_age_in_months_on(date: NaiveDate, expected: Option<i32>) {
+        let birthday = BirthdayDate {
+            day: 15,
+            month: 1,
+            year: Some(2024),
+        };
+        assert_eq!(birthday.age_in_months_on(date), expected);
+    }
+
+    #[test_case(Some(2909), true ; "Future year")]
+    #[test_case(Some(2024), false ; "Current year")]
+    #[test_case(Some(1909), false ; "Past year")]
+    #[test_case(None, false ; "Unknown year")]
+    fn test_has_future_year(year: Option<i32>, expected: bool) {
+        let birthday = BirthdayDate {
+            day: 1,
+            month: 1,
+            year,
+        };
+        assert_eq!(
+            birthday.has_future_year(NaiveDate::from_ymd_opt(2024, 6, 6).unwrap()),
+            expected
+        );
+    }
+
+    #[test_case(EntryDate::Month(MonthDate { month: 3, year: Some(2909) }), true ; "Future year without a day")]
+    #[test_case(EntryDate::Month(MonthDate { month: 3, year: Some(1909) }), false ; "Past year without a day")]
+    #[test_case(EntryDate::Weekday(WeekdayDate { week_of_month: 2, weekday: Weekday::Sun, month: 5 }), false ; "Weekday date")]
+    fn test_entry_date_has_future_year(date: EntryDate, expected: bool) {
+        assert_eq!(
+            date.has_future_year(NaiveDate::from_ymd_opt(2024, 6, 6).unwrap()),
+            expected
+        );
+    }
+
+    #[test_case("24/12/1990", true ; "Valid year")]
+    #[test_case("24/12/9999", true ; "Largest year")]
+    #[test_case("24/12/0", false ; "Year 0")]
+    #[test_case("24/12/-44", false ; "Negative year")]
+    #[test_case("24/12/2147483647", false ; "Huge year")]
+    fn test_birthday_date_year_range(date: &str, valid: bool) {
+        assert_eq!(date.parse::<BirthdayDate>().is_ok(), valid);
+    }
+
+    #[test_case(Some(1990), Some(Weekday::Wed) ; "Known year")]
+    #[test_case(Some(2000), Some(Weekday::Tue) ; "Leap year")]
+    #[test_case(None, None ; "Unknown year")]
+    fn test_birth_weekday(year: Option<i32>, expected: Option<Weekday>) {
+        let birthday = BirthdayDate {
+            day: 6,
+            month: 6,
+            year,
+        };
+        assert_eq!(birthday.birth_weekday(), expected);
+    }
+
+    #[test_case("day = 1\nmonth = 13" ; "Month out of range")]
+    #[test_case("day = 31\nmonth = 4" ; "Day out of range")]
+    #[test_case("day = 29\nmonth = 2\nyear = 2023" ; "February 29th on a non-leap year")]
+    #[test_case("week_of_month = 6\nweekday = \"Sun\"\nmonth = 5" ; "Week of month out of range")]
+    #[test_case("month = 13" ; "Month out of range, without a day")]
+    #[test_case("weekday = \"Sun\"\nmonth = 5" ; "Weekday without a week of month")]
+    #[test_case("day = 1\nmonth = 1\nyear = 0" ; "Year 0")]
+    #[test_case("day = 1\nmonth = 1\nyear = -44" ; "Negative year")]
+    #[test_case("month = 1\nyear = 100000" ; "Huge year, without a day")]
+    fn test_invalid_date_in_file(date: &str) {
+        let file = format!("[[birthdays]]\nname = \"A\"\n{}\n", date);
+        assert!(toml::from_str::<Config>(&file).is_err());
+    }
+
+    #[test]
+    fn test_month_date_in_file() {
+        let file = "[[birthdays]]\nname = \"A\"\nmonth = 5\nyear = 1990\n";
+        let config = toml::from_str::<Config>(file).unwrap();
+        assert_eq!(
+            config.birthdays[0].date,
+            EntryDate::Month(MonthDate {
+                month: 5,
+                year: Some(1990)
+            })
+        );
+        assert_eq!(
+            toml::to_string(&config.birthdays[0]).unwrap(),
+            "name = \"A\"\nmonth = 5\nyear = 1990\n"
+        );
+    }
+
+    #[test_case(NaiveDate::from_ymd_opt(2024, 3, 20).unwrap(), 12, Some(34) ; "Before the month")]
+    #[test_case(NaiveDate::from_ymd_opt(2024, 4, 20).unwrap(), 0, Some(34) ; "During the month")]
+    #[test_case(NaiveDate::from_ymd_opt(2024, 5, 20).unwrap(), 316, Some(35) ; "After the month")]
+    fn test_entry_month_date(today: NaiveDate, days: i64, next_age: Option<i32>) {
+        let date = EntryDate::Month(MonthDate {
+            month: 4,
+            year: Some(1990),
+        });
+        let entry = Entry::from_config(config_entry(date), Some(today), LeapDay::Feb28).unwrap();
+        assert!(entry.day_unknown);
+        assert_eq!(entry.num_days_until(), days);
+        assert_eq!(entry.next_age(), next_age);
+    }
+
+    #[test_case("", Some(EntryKind::Birthday) ; "Birthday by default")]
+    #[test_case("kind = \"anniversary\"\n", Some(EntryKind::Anniversary) ; "Anniversary")]
+    #[test_case("kind = \"wedding\"\n", None ; "Unknown kind")]
+    fn test_kind_in_file(kind: &str, expected: Option<EntryKind>) {
+        let entry = format!("name = \"A\"\nday = 1\nmonth = 5\n{}", kind);
+        let config = toml::from_str::<Config>(&format!("[[birthdays]]\n{}", entry)).ok();
+        assert_eq!(
+            config.as_ref().map(|config| config.birthdays[0].kind),
+            expected
+        );
+        // The default kind isn't written back
+        if let Some(config) = config {
+            assert_eq!(toml::to_string(&config.birthdays[0]).unwrap(), entry);
+        }
+    }
+
+    #[test_case("borderless", Some(TableStyle::Borderless) ; "Known style")]
+    #[test_case("fancy", None ; "Unknown style")]
+    fn test_table_style_in_file(style: &str, expected: Option<TableStyle>) {
+        let file = format!("birthdays = []\n[display]\ntable_style = \"{}\"\n", style);
+        assert_eq!(
+            toml::from_str::<Config>(&file)
+                .ok()
+                .map(|config| config.display.table_style),
+            expected
+        );
+    }
+
+    #[test_case("+05:30", Some(5 * 3600 + 30 * 60) ; "Hours and minutes")]
+    #[test_case("-08:00", Some(-8 * 3600) ; "Negative offset")]
+    #[test_case("+9", Some(9 * 3600) ; "Hours only")]
+    #[test_case("UTC+5:30", Some(5 * 3600 + 30 * 60) ; "UTC prefix")]
+    #[test_case("+05:60", None ; "Minutes out of range")]
+    #[test_case("+25:00", None ; "Hours out of range")]
+    #[test_case("05:30", None ; "No sign")]
+    fn test_fixed_offset_timezone(timezone: &str, expected: Option<i32>) {
+        assert_eq!(
+            timezone.parse::<EntryTimezone>().ok(),
+            expected.map(|secs| EntryTimezone::Fixed(FixedOffset::east_opt(secs).unwrap()))
+        );
+    }
+
+    #[test]
+    fn test_named_timezone() {
+        assert_eq!(
+            "europe/paris".parse::<EntryTimezone>(),
+            Ok(EntryTimezone::Named(chrono_tz::Europe::Paris))
+        );
+    }
+
+    /// An entry of january 2030, starting at the given hour
+    fn january_entry(name: &str, day: u32, hour: u32) -> Entry {
+        Entry {
+            name: name.to_string(),
+            aliases: vec![],
+            first_name: None,
+            last_name: None,
+            budget: None,
+            photo: None,
+            email: None,
+            relationship: None,
+            kind: EntryKind::Birthday,
+            date: BirthdayDate {
+                day,
+                month: 1,
+                year: None,
+            },
+            day_unknown: false,
+            timezone: None,
+            time: None,
+            source: None,
+            leap_day: LeapDay::Feb28,
+            today: NaiveDate::from_ymd_opt(2030, 1, 1).unwrap(),
+            prev_occurence: None,
+            next_occurence: Some(Local.with_ymd_and_hms(2030, 1, day, hour, 0, 0).unwrap()),
+        }
+    }
+
+    #[test]
+    fn test_sort_entries_same_day_by_name() {
+        let entry = |name: &str, day: u32| january_entry(name, day, 0);
+        let mut entries = vec![entry("bob", 2), entry("Carol", 1), entry("alice", 2)];
+        sort_entries(&mut entries);
+        let names: Vec<&str> = entries.iter().map(|entry| entry.name.as_str()).collect();
+        assert_eq!(names, vec!["alice", "bob", "Carol"]);
+    }
+
+    #[test]
+    fn test_sort_entries_mixes_kinds_of_dates() {
+        // The second sunday of may 2024 is the 12th, before the 13th even though its ordinal in the month is after
+        let today = NaiveDate::from_ymd_opt(2024, 5, 1).unwrap();
+        let dates = [
+            (
+                "June",
+                EntryDate::Month(MonthDate {
+                    month: 6,
+                    year: None,
+                }),
+            ),
+            (
+                "Mother's day",
+                EntryDate::Weekday(WeekdayDate {
+                    week_of_month: 2,
+                    weekday: Weekday::Sun,
+                    month: 5,
+                }),
+            ),
+            (
+                "13 May",
+                EntryDate::Date(BirthdayDate {
+                    day: 13,
+                    month: 5,
+                    year: None,
+                }),
+            ),
+            (
+                "10 May",
+                EntryDate::Date(BirthdayDate {
+                    day: 10,
+                    month: 5,
+                    year: None,
+                }),
+            ),
+        ];
+        let mut entries: Vec<Entry> = dates
+            .into_iter()
+            .map(|(name, date)| {
+                let mut config_entry = config_entry(date);
+                config_entry.name = name.to_string();
+                Entry::from_config(config_entry, Some(today), LeapDay::Feb28).unwrap()
+            })
+            .collect();
+        sort_entries(&mut entries);
+        let names: Vec<&str> = entries.iter().map(|entry| entry.name.as_str()).collect();
+        // Sorted from the farthest to the closest
+        assert_eq!(names, vec!["June", "13 May", "Mother's day", "10 May"]);
+    }
+
+    #[test_case(false, vec!["Carol", "Adam", "Zoé", "Bob"] ; "Ascending")]
+    #[test_case(true, vec!["Bob", "Adam", "Zoé", "Carol"] ; "Descending")]
+    fn test_sort_entries_by_day(descending: bool, expected: Vec<&str>) {
+        // Adam and Zoé share a day, but Zoé's birthday starts first because of her timezone
+        let entries = [
+            january_entry("Zoé", 5, 0),
+            january_entry("Bob", 20, 0),
+            january_entry("Adam", 5, 10),
+            january_entry("Carol", 1, 0),
+        ];
+        let mut entries: Vec<&Entry> = entries.iter().collect();
+        sort_entries_by_day(&mut entries, descending);
+        let names: Vec<&str> = entries.iter().map(|entry| entry.name.as_str()).collect();
+        assert_eq!(names, expected);
+    }
+
+    #[test_case(false, vec!["Mo", "Carol", "Zoé", "Bob"] ; "Ascending")]
+    #[test_case(true, vec!["Bob", "Zoé", "Carol", "Mo"] ; "Descending")]
+    fn test_sort_entries_by_calendar(descending: bool, expected: Vec<&str>) {
+        // Mo was born in january on an unknown day, and Bob's birthday in february is the closest one
+        let mut month_only = january_entry("Mo", 1, 0);
+        month_only.day_unknown = true;
+        let mut february = january_entry("Bob", 3, 0);
+        february.date.month = 2;
+        let entries = [
+            january_entry("Zoé", 5, 0),
+            february,
+            month_only,
+            january_entry("Carol", 1, 0),
+        ];
+        let mut entries: Vec<&Entry> = entries.iter().collect();
+        sort_entries_by_calendar(&mut entries, descending);
+        let names: Vec<&str> = entries.iter().map(|entry| entry.name.as_str()).collect();
+        assert_eq!(names, expected);
+    }
+
+    #[test_case(Some("Rintaro"), Some("Okabe"), "Okabe, Rintaro" ; "First and last name")]
+    #[test_case(None, Some("Okabe"), "Okabe" ; "Only the last name")]
+    #[test_case(Some("Rintaro"), None, "Okarin" ; "Only the first name")]
+    #[test_case(None, None, "Okarin" ; "No split name")]
+    fn test_reversed_name(first_name: Option<&str>, last_name: Option<&str>, expected: &str) {
+        let mut entry = january_entry("Okarin", 1, 0);
+        entry.first_name = first_name.map(str::to_string);
+        entry.last_name = last_name.map(str::to_string);
+        assert_eq!(entry.reversed_name(), expected);
+    }
+
+    #[test_case(&[], 30, true ; "Multiple of 10 by default")]
+    #[test_case(&[], 21, false ; "Other age by default")]
+    #[test_case(&[], 0, false ; "Birth by default")]
+    #[test_case(&[18, 21, 65], 21, true ; "Configured age")]
+    #[test_case(&[18, 21, 65], 30, false ; "Multiple of 10 not configured")]
+    fn test_is_milestone(milestones: &[i32], age: i32, expected: bool) {
+        let options = OptionsConfig {
+            milestones: milestones.to_vec(),
+            ..Default::default()
+        };
+        assert_eq!(options.is_milestone(age), expected);
+    }
+
+    #[test]
+    fn test_edits_keep_comments() {
+        let toml_str =
+            "# My birthdays\n\n[[birthdays]]\nname = \"A\" # best friend\nday = 1\nmonth = 3\n";
+        let mut conf_file = ConfigFile {
+            path: "bday.toml".into(),
+            config: toml::from_str(toml_str).unwrap(),
+            document: toml_str.parse().unwrap(),
+        };
+        conf_file.rename_entry(0, "Alice");
+        conf_file.add_entry(ConfigEntry {
+            name: "Bob".to_string(),
+            date: EntryDate::Date(BirthdayDate {
+                day: 2,
+                month: 2,
+                year: None,
+            }),
+            timezone: None,
+            hidden: false,
+            aliases: vec![],
+            first_name: None,
+            last_name: None,
+            budget: None,
+            photo: None,
+            email: None,
+            relationship: None,
+            kind: EntryKind::Birthday,
+            time: None,
+            source: None,
+        });
+        assert_eq!(
+            conf_file.document.to_string(),
+            "# My birthdays\n\n[[birthdays]]\nname = \"Alice\" # best friend\nday = 1\nmonth = 3\n\n[[birthdays]]\nname = \"Bob\"\nday = 2\nmonth = 2\n"
+        );
+    }
+
+    #[test]
+    fn test_remove_entries() {
+        let toml_str = "[[birthdays]]\nname = \"A\"\nday = 1\nmonth = 3\n\n[[birthdays]]\nname = \"B\" # keep\nday = 2\nmonth = 3\n\n[[birthdays]]\nname = \"C\"\nday = 3\nmonth = 3\n";
+        let mut conf_file = ConfigFile {
+            path: "bday.toml".into(),
+            config: toml::from_str(toml_str).unwrap(),
+            document: toml_str.parse().unwrap(),
+        };
+        conf_file.remove_entries(&[0, 2]);
+        assert_eq!(conf_file.config.birthdays.len(), 1);
+        assert_eq!(conf_file.config.birthdays[0].name, "B");
+        assert_eq!(
+            conf_file.document.to_string(),
+            "\n[[birthdays]]\nname = \"B\" # keep\nday = 2\nmonth = 3\n"
+        );
+    }
+
+    #[test]
+    fn test_normalize_file() {
+        let toml_str = "version = 1\nbirthdays = [{ month = 5, name = 'Zoe', day = 3, timezone = 'europe/paris' }, { name = \"Al\", day = 1, month = 2, hidden = false }]\n";
+        let mut conf_file = ConfigFile {
+            path: "bday.toml".into(),
+            config: toml::from_str(toml_str).unwrap(),
+            document: toml_str.parse().unwrap(),
+        };
+        conf_file.normalize();
+        let expected = "version = 1\nbirthdays = [{ name = \"Al\", day = 1, month = 2 }, { name = \"Zoe\", day = 3, month = 5, timezone = \"Europe/Paris\" }]\n";
+        assert_eq!(conf_file.document.to_string(), expected);
+        assert_eq!(conf_file.config.birthdays[0].name, "Al");
+        // Formatting again changes nothing
+        conf_file.normalize();
+        assert_eq!(conf_file.document.to_string(), expected);
+    }
+
+    #[test]
+    fn test_normalize_file_keeps_comments() {
+        let toml_str = "# My birthdays\nversion = 1\n\n# college friend\n[[birthdays]]\nmonth = 5\nname = \"Zoe\" # nickname\nday = 3\n\n[[birthdays]]\nname = \"Al\"\nday = 1 # maybe the 2nd\nmonth = 2\n\n# How the table looks\n[display]\ntable_style = \"ascii\"\n";
+        let mut conf_file = ConfigFile {
+            path: "bday.toml".into(),
+            config: toml::from_str(toml_str).unwrap(),
+            document: toml_str.parse().unwrap(),
+        };
+        conf_file.normalize();
+        let formatted = conf_file.document.to_string();
+        assert_eq!(
+            formatted,
+            "# My birthdays\nversion = 1\n\n[[birthdays]]\nname = \"Al\"\nday = 1 # maybe the 2nd\nmonth = 2\n\n# college friend\n[[birthdays]]\nname = \"Zoe\" # nickname\nday = 3\nmonth = 5\n\n# How the table looks\n[display]\ntable_style = \"ascii\"\n"
+        );
+        // The formatted file reads back the same, and formatting it again changes nothing
+        let mut conf_file = ConfigFile {
+            path: "bday.toml".into(),
+            config: toml::from_str(&formatted).unwrap(),
+            document: formatted.parse().unwrap(),
+        };
+        conf_file.normalize();
+        assert_eq!(conf_file.document.to_string(), formatted);
+    }
+
+    /// An entry with only a name and a date
+    fn config_entry(date: EntryDate) -> ConfigEntry {
+        ConfigEntry {
+            name: "A".to_string(),
+            date,
+            timezone: None,
+            hidden: false,
+            aliases: vec![],
+            first_name: None,
+            last_name: None,
+            budget: None,
+            photo: None,
+            email: None,
+            relationship: None,
+            kind: EntryKind::Birthday,
+            time: None,
+            source: None,
+        }
+    }
+
+    #[test]
+    fn test_entry_from_config_relative_to() {
+        let date = EntryDate::Date(BirthdayDate {
+            day: 20,
+            month: 10,
+            year: Some(1990),
+        });
+        let today = NaiveDate::from_ymd_opt(2024, 10, 14).unwrap();
+        let entry = Entry::from_config(config_entry(date), Some(today), LeapDay::Feb28).unwrap();
+        assert_eq!(entry.age_now(), Some(33));
+        assert_eq!(entry.next_age(), Some(34));
+    }
+
+    #[test_case(EntryDate::Date(BirthdayDate { day: 20, month: 10, year: None }), NaiveDate::from_ymd_opt(2024, 10, 14).unwrap(), Some((NaiveDate::from_ymd_opt(2023, 10, 20).unwrap(), NaiveDate::from_ymd_opt(2024, 10, 20).unwrap())) ; "Later this year")]
+    #[test_case(EntryDate::Date(BirthdayDate { day: 1, month: 3, year: None }), NaiveDate::from_ymd_opt(2024, 10, 14).unwrap(), Some((NaiveDate::from_ymd_opt(2024, 3, 1).unwrap(), NaiveDate::from_ymd_opt(2025, 3, 1).unwrap())) ; "Next year")]
+    #[test_case(EntryDate::Date(BirthdayDate { day: 20, month: 10, year: None }), NaiveDate::from_ymd_opt(2024, 10, 20).unwrap(), None ; "Today")]
+    #[test_case(EntryDate::Date(BirthdayDate { day: 29, month: 2, year: None }), NaiveDate::from_ymd_opt(2023, 3, 1).unwrap(), Some((NaiveDate::from_ymd_opt(2023, 2, 28).unwrap(), NaiveDate::from_ymd_opt(2024, 2, 29).unwrap())) ; "February 29th, from a non-leap year")]
+    #[test_case(EntryDate::Weekday(WeekdayDate { week_of_month: 2, weekday: Weekday::Sun, month: 5 }), NaiveDate::from_ymd_opt(2024, 5, 1).unwrap(), Some((NaiveDate::from_ymd_opt(2023, 5, 14).unwrap(), NaiveDate::from_ymd_opt(2024, 5, 12).unwrap())) ; "Second sunday of may")]
+    fn test_entry_occurences(
+        date: EntryDate,
+        today: NaiveDate,
+        expected: Option<(NaiveDate, NaiveDate)>,
+    ) {
+        let entry = Entry::from_config(config_entry(date), Some(today), LeapDay::Feb28).unwrap();
+        assert_eq!(
+            entry
+                .prev_occurence
+                .zip(entry.next_occurence)
+                .map(|(prev, next)| { (prev.date_naive(), next.date_naive()) }),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_entry_occurence_time() {
+        let mut config_entry = config_entry(EntryDate::Date(BirthdayDate {
+            day: 20,
+            month: 10,
+            year: None,
+        }));
+        config_entry.time = NaiveTime::from_hms_opt(9, 30, 0);
+        let today = NaiveDate::from_ymd_opt(2024, 10, 14).unwrap();
+        let entry = Entry::from_config(config_entry, Some(today), LeapDay::Feb28).unwrap();
+        assert_eq!(
+            entry.next_occurence.map(|dt| dt.naive_local()),
+            NaiveDate::from_ymd_opt(2024, 10, 20).and_then(|date| date.and_hms_opt(9, 30, 0))
+        );
+    }
+
+    // Midnight of october 15th is at 10:00 UTC on the 14th in UTC+14, and at 10:00 UTC on the 15th in UTC-10
+    #[test_case("+14:00", Utc.with_ymd_and_hms(2024, 10, 14, 9, 59, 59).unwrap(), 2 ; "Ahead of UTC, just before midnight")]
+    #[test_case("+14:00", Utc.with_ymd_and_hms(2024, 10, 14, 10, 0, 0).unwrap(), 1 ; "Ahead of UTC, at midnight")]
+    #[test_case("-10:00", Utc.with_ymd_and_hms(2024, 10, 15, 9, 59, 59).unwrap(), 2 ; "Behind UTC, just before midnight")]
+    #[test_case("-10:00", Utc.with_ymd_and_hms(2024, 10, 15, 10, 0, 0).unwrap(), 1 ; "Behind UTC, at midnight")]
+    fn test_entry_num_days_until_near_midnight(timezone: &str, now: DateTime<Utc>, days: i64) {
+        utils::set_now(now);
+        let mut config_entry = config_entry(EntryDate::Date(BirthdayDate {
+            day: 16,
+            month: 10,
+            year: None,
+        }));
+        config_entry.timezone = Some(timezone.to_string());
+        // Without a date to compute from, the entry uses the current time in its timezone
+        let entry = Entry::from_config(config_entry, None, LeapDay::Feb28).unwrap();
+        assert_eq!(entry.num_days_until(), days);
+    }
+
+    #[test]
+    fn test_parse_config_without_version() {
+        let toml_str = "[[birthdays]]\nname = \"A\"\nday = 1\nmonth = 3\n";
+        let Ok(conf_file) = parse_config("bday.toml".as_ref(), toml_str) else {
+            panic!("The config should be valid");
+        };
+        assert_eq!(conf_file.config.version, CONFIG_VERSION);
+        assert_eq!(
+            conf_file.document["version"].as_integer(),
+            Some(i64::from(CONFIG_VERSION))
+        );
+        assert_eq!(conf_file.config.birthdays.len(), 1);
+    }
+
+    #[test]
+    fn test_parse_config_newer_version() {
+        let toml_str = format!(
+            "version = {}\n[[birthdays]]\nname = \"A\"\nday = 1\nmonth = 3\n",
+            CONFIG_VERSION + 1
+        );
+        assert!(matches!(
+            parse_config("bday.toml".as_ref(), &toml_str),
+            Err(LoadConfigError::NewerVersion(version)) if version == CONFIG_VERSION + 1
+        ));
+    }
+
+    #[test]
+    fn test_parse_config_error_has_path() {
+        let toml_str = "[[birthdays]]\nname = \"A\"\nday = 31\nmonth = 4\n";
+        assert!(matches!(
+            parse_config("work/bday.toml".as_ref(), toml_str),
+            Err(LoadConfigError::TomlError(path, _)) if path == std::path::Path::new("work/bday.toml")
+        ));
+    }
 }
@@ -0,0 +1,79 @@
+use crate::config::{BirthdayDate, ConfigEntry, EntryDate, EntryKind};
+use chrono::NaiveDate;
+use rand::rngs::SmallRng;
+use rand::{Rng, SeedableRng};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const NAMES: [&str; 12] = [
+    "Okabe Rintaro",
+    "Shiina Mayuri",
+    "Hashida Itaru",
+    "Makise Kurisu",
+    "Kiryu Moeka",
+    "Urushibara Ruka",
+    "Akiha Rumiho",
+    "Amane Suzuha",
+    "Hiyajo Maho",
+    "Alexis Leskinen",
+    "Tennouji Yuugo",
+    "Tennouji Nae",
+];
+
+const TIMEZONES: [&str; 6] = [
+    "Asia/Tokyo",
+    "America/Los_Angeles",
+    "America/New_York",
+    "Europe/Paris",
+    "Europe/London",
+    "Australia/Sydney",
+];
+
+/// Generate random but plausible entries.
+/// The same seed always generates the same entries, if no seed is given one is picked from the current time.
+pub fn generate_entries(count: usize, seed: Option<u64>) -> Vec<ConfigEntry> {
+    let seed = seed.unwrap_or_else(|| {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default()
+    });
+    let mut rng = SmallRng::seed_from_u64(seed);
+
+    (0..count)
+        .map(|_| {
+            // Pick a random day of a leap year, so february 29th can be generated too
+            let day_of_year = rng.gen_range(1..=366);
+            let date = NaiveDate::from_yo_opt(2000, day_of_year).unwrap();
+            let year = if rng.gen_bool(0.7) {
+                Some(rng.gen_range(1950..=2020))
+            } else {
+                None
+            };
+            let mut date = BirthdayDate::from(date);
+            // February 29th only exists on leap years
+            date.year =
+                year.filter(|&year| NaiveDate::from_ymd_opt(year, date.month, date.day).is_some());
+
+            ConfigEntry {
+                name: NAMES[rng.gen_range(0..NAMES.len())].to_string(),
+                date: EntryDate::Date(date),
+                timezone: if rng.gen_bool(0.3) {
+                    Some(TIMEZONES[rng.gen_range(0..TIMEZONES.len())].to_string())
+                } else {
+                    None
+                },
+                hidden: false,
+                aliases: vec![],
+                first_name: None,
+                last_name: None,
+                budget: None,
+                photo: None,
+                email: None,
+                relationship: None,
+                kind: EntryKind::Birthday,
+                time: None,
+                source: None,
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,118 @@
+use crate::config::{BirthdayDate, ConfigEntry, EntryDate, EntryKind, EntryTimezone, MonthDate};
+use serde::Deserialize;
+use std::path::Path;
+
+/// A row of a CSV file, with the same columns as the generic CSV export
+#[derive(Deserialize)]
+struct Row {
+    name: String,
+    /// Empty if only the month is known
+    day: Option<u32>,
+    month: u32,
+    year: Option<i32>,
+    timezone: Option<String>,
+}
+
+impl TryFrom<Row> for ConfigEntry {
+    type Error = String;
+
+    fn try_from(row: Row) -> Result<Self, Self::Error> {
+        if row.name.trim().is_empty() {
+            return Err("the name is empty".to_string());
+        }
+        let date = match row.day {
+            Some(day) => EntryDate::Date(
+                BirthdayDate::new(day, row.month, row.year).map_err(|e| e.to_lowercase())?,
+            ),
+            None => {
+                EntryDate::Month(MonthDate::new(row.month, row.year).map_err(|e| e.to_lowercase())?)
+            }
+        };
+        let timezone = match row.timezone {
+            Some(tz) => Some(tz.parse::<EntryTimezone>()?.to_string()),
+            None => None,
+        };
+        Ok(ConfigEntry {
+            name: row.name.trim().to_string(),
+            date,
+            timezone,
+            hidden: false,
+            aliases: vec![],
+            first_name: None,
+            last_name: None,
+            budget: None,
+            photo: None,
+            email: None,
+            relationship: None,
+            kind: EntryKind::Birthday,
+            time: None,
+            source: None,
+        })
+    }
+}
+
+/// Read the entries of a CSV file with the columns name, day, month, year and timezone, separated by the given character.
+/// Each row gives either an entry or the reason it is invalid, along with its line number.
+pub fn read_csv(
+    path: &Path,
+    separator: u8,
+) -> csv::Result<Vec<(u64, Result<ConfigEntry, String>)>> {
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(separator)
+        .from_path(path)?;
+    Ok(reader
+        .deserialize::<Row>()
+        .enumerate()
+        .map(|(index, row)| {
+            // The first line is the header
+            let line = row
+                .as_ref()
+                .err()
+                .and_then(|e| e.position())
+                .map_or(index as u64 + 2, |position| position.line());
+            let entry = match row {
+                Ok(row) => ConfigEntry::try_from(row),
+                Err(e) => Err(match e.kind() {
+                    csv::ErrorKind::Deserialize { err, .. } => err.to_string(),
+                    _ => e.to_string(),
+                }),
+            };
+            (line, entry)
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Row;
+    use crate::config::ConfigEntry;
+    use test_case::test_case;
+
+    #[test_case("Alice", Some(1), 3, None, None, true ; "Valid")]
+    #[test_case("Alice", Some(29), 2, Some(2000), Some("+05:30"), true ; "Valid, with year and timezone")]
+    #[test_case(" ", Some(1), 3, None, None, false ; "Empty name")]
+    #[test_case("Alice", Some(29), 2, Some(2001), None, false ; "February 29th on a non-leap year")]
+    #[test_case("Alice", Some(1), 13, None, None, false ; "Invalid month")]
+    #[test_case("Alice", Some(1), 3, None, Some("Mars/Base"), false ; "Invalid timezone")]
+    #[test_case("Alice", None, 3, Some(1990), None, true ; "Without a day")]
+    #[test_case("Alice", None, 13, None, None, false ; "Invalid month, without a day")]
+    #[test_case("Alice", Some(1), 3, Some(0), None, false ; "Year 0")]
+    #[test_case("Alice", None, 3, Some(-44), None, false ; "Negative year, without a day")]
+    fn test_row_to_entry(
+        name: &str,
+        day: Option<u32>,
+        month: u32,
+        year: Option<i32>,
+        timezone: Option<&str>,
+        valid: bool,
+    ) {
+        let row = Row {
+            name: name.to_string(),
+            day,
+            month,
+            year,
+            timezone: timezone.map(str::to_string),
+        };
+        assert_eq!(ConfigEntry::try_from(row).is_ok(), valid);
+    }
+}
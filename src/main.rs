@@ -1,211 +1,2287 @@
-use chrono::{DateTime, Datelike, Local, NaiveDate};
-use chrono_humanize::HumanTime;
+use chrono::{DateTime, Local, NaiveDate, TimeDelta, Utc, Weekday};
+use chrono_humanize::{Accuracy, HumanTime, Tense};
 // use clap::error::Result;
 // use clap::{error::Error, error::ErrorKind, Command, CommandFactory, Parser, Subcommand};
 use clap::{error::ErrorKind, CommandFactory, Parser};
 use directories::BaseDirs;
-use prettytable::{format, row, Table};
-use std::path::PathBuf;
-use std::{fs, process::exit};
+use prettytable::{cell, format, Cell, Row, Table};
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::io::{IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::process::{exit, Command, Stdio};
+use std::sync::OnceLock;
+use std::thread;
+use std::time::Duration;
+use std::{fmt, fs, io};
 
+mod calendar;
 mod cli;
 mod config;
+mod demo;
+mod import;
+mod stats;
 mod utils;
 
+/// How errors are printed, set once the arguments are parsed
+static ERROR_FORMAT: OnceLock<cli::ErrorFormat> = OnceLock::new();
+
+/// What an error is about, given as the "kind" of errors in the JSON format
+#[derive(Debug, Clone, Copy)]
+enum ErrorCategory {
+    /// Reading, parsing or modifying the birthday file isn't possible
+    Config,
+    /// The timezone of an entry is invalid
+    Timezone,
+    /// Writing files, running other programs or reading answers failed
+    Io,
+    /// A value given by the user can't be used, even if the command is valid
+    Value,
+    /// The command is invalid
+    Usage,
+}
+
+impl ErrorCategory {
+    fn name(&self) -> &'static str {
+        match self {
+            ErrorCategory::Config => "config",
+            ErrorCategory::Timezone => "timezone",
+            ErrorCategory::Io => "io",
+            ErrorCategory::Value => "value",
+            ErrorCategory::Usage => "usage",
+        }
+    }
+}
+
+/// Print the error as a JSON object on stderr, such as {"error": "...", "kind": "io"}
+fn print_json_error(category: ErrorCategory, message: impl fmt::Display) {
+    eprintln!(
+        "{{\"error\": {}, \"kind\": {}}}",
+        utils::json_string(&message.to_string()),
+        utils::json_string(category.name())
+    );
+}
+
+fn json_errors() -> bool {
+    ERROR_FORMAT.get() == Some(&cli::ErrorFormat::Json)
+}
+
+/// Print an error that isn't about how the command was used (so without clap's usage section), then exit with code 3
+fn exit_with_error(category: ErrorCategory, message: impl fmt::Display) -> ! {
+    if json_errors() {
+        print_json_error(category, message);
+    } else {
+        let kind = match category {
+            ErrorCategory::Value => ErrorKind::ValueValidation,
+            _ => ErrorKind::Io,
+        };
+        let _ = clap::Error::raw(kind, format!("{}\n", message)).print();
+    }
+    exit(3);
+}
+
+/// Print an error about how the command was used, with clap's usage section, then exit with code 2
+fn exit_with_usage_error(kind: ErrorKind, message: impl fmt::Display) -> ! {
+    if json_errors() {
+        print_json_error(ErrorCategory::Usage, message);
+        exit(2);
+    }
+    cli::Cli::command().error(kind, message).exit()
+}
+
+/// A mailto link to send a birthday message to the email address, with the given message if there is one
+fn mailto_link(email: &str, message: Option<&str>) -> String {
+    let mut link = format!(
+        "mailto:{}?subject={}",
+        email,
+        utils::percent_encode("Happy birthday!")
+    );
+    if let Some(message) = message {
+        link.push_str(&format!("&body={}", utils::percent_encode(message)));
+    }
+    link
+}
+
+/// Open a link with the default application of the system, such as the mail client for mailto links
+fn open_link(link: &str) {
+    let result = if cfg!(target_os = "windows") {
+        Command::new("cmd").args(["/C", "start", "", link]).status()
+    } else if cfg!(target_os = "macos") {
+        Command::new("open").arg(link).status()
+    } else {
+        Command::new("xdg-open").arg(link).status()
+    };
+    match result {
+        Ok(status) if status.success() => {}
+        Ok(status) => exit_with_error(
+            ErrorCategory::Io,
+            format!(
+                "Error opening {}: the command failed with {}.",
+                link, status
+            ),
+        ),
+        Err(e) => exit_with_error(ErrorCategory::Io, format!("Error opening {}: {}.", link, e)),
+    }
+}
+
+/// Put the text on the clipboard using the clipboard command of the system,
+/// returning false if none of them is available (for example without a graphical session)
+fn copy_to_clipboard(text: &str) -> bool {
+    let commands: &[&[&str]] = if cfg!(target_os = "windows") {
+        &[&["clip"]]
+    } else if cfg!(target_os = "macos") {
+        &[&["pbcopy"]]
+    } else {
+        &[
+            &["wl-copy"],
+            &["xclip", "-selection", "clipboard"],
+            &["xsel", "--clipboard", "--input"],
+        ]
+    };
+    commands.iter().any(|command| {
+        let Ok(mut child) = Command::new(command[0])
+            .args(&command[1..])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+        else {
+            return false;
+        };
+        let written = child
+            .stdin
+            .take()
+            .is_some_and(|mut stdin| stdin.write_all(text.as_bytes()).is_ok());
+        child.wait().is_ok_and(|status| status.success()) && written
+    })
+}
+
+/// Write the config back to its birthday file, after backing up its previous content so it can be undone.
+/// The content is written to a temporary file first, so the birthday file is never left half-written.
+fn write_config(conf_file: &config::ConfigFile) {
+    if conf_file.path.exists() {
+        if let Err(e) = fs::copy(&conf_file.path, config::backup_path(&conf_file.path)) {
+            exit_with_error(
+                ErrorCategory::Io,
+                format!("Error backing up the birthday file: {}", e),
+            );
+        }
+    }
+    if let Err(e) = replace_file(&conf_file.path, conf_file.document.to_string().as_bytes()) {
+        exit_with_error(
+            ErrorCategory::Io,
+            format!("Error writing the birthday file: {}", e),
+        );
+    }
+}
+
+/// Write the content to an adjacent temporary file renamed over the file,
+/// so the file is never left half written
+fn replace_file(path: &Path, content: &[u8]) -> io::Result<()> {
+    let mut tmp_path = path.as_os_str().to_owned();
+    tmp_path.push(".tmp");
+    fs::write(&tmp_path, content).and_then(|()| fs::rename(&tmp_path, path))
+}
+
+/// The names that are in the first list more times than in the second one
+fn missing_names<'a>(names: &[&'a str], other: &[&str]) -> Vec<&'a str> {
+    let mut other = other.to_vec();
+    names
+        .iter()
+        .filter(|name| match other.iter().position(|other| other == *name) {
+            Some(position) => {
+                other.remove(position);
+                false
+            }
+            None => true,
+        })
+        .copied()
+        .collect()
+}
+
+/// Describe a duration in words, such as "in 3 weeks"
+fn humanize(duration: TimeDelta, humanize: config::Humanize) -> String {
+    match humanize {
+        config::Humanize::Rough => HumanTime::from(duration).to_string(),
+        // Without rounding, the seconds would always be displayed
+        config::Humanize::Precise => HumanTime::from(TimeDelta::minutes(duration.num_minutes()))
+            .to_text_en(Accuracy::Precise, Tense::Future),
+    }
+}
+
+/// Describe how long until the given time, such as "Tomorrow" or "in 3 weeks".
+/// The day difference is used for close times, as durations of less than a day can be confusing.
+fn humanize_until(
+    dt: DateTime<Local>,
+    now: DateTime<Local>,
+    precision: config::Humanize,
+) -> String {
+    match utils::relative_day(utils::days_between(&now, &dt)) {
+        Some(day) => day.to_string(),
+        None => humanize(dt - now, precision),
+    }
+}
+
+/// Format a number of years, such as "1 year" or "25 years"
+fn format_years(years: i32) -> String {
+    format!("{} year{}", years, if years == 1 { "" } else { "s" })
+}
+
+/// Describe the age an entry turns on its birthday of the given year, or on its next birthday without a year,
+/// such as "turning 35", or None if the year of birth is unknown
+fn describe_age_on_day(entry: &config::Entry, year: Option<i32>) -> Option<String> {
+    let age = match year {
+        Some(year) => year - entry.date.year?,
+        None => entry.next_age()?,
+    };
+    Some(match (age, entry.kind) {
+        (..0, config::EntryKind::Birthday) => "not born yet".to_string(),
+        (..0, config::EntryKind::Anniversary) => "not happened yet".to_string(),
+        (0, config::EntryKind::Birthday) => "born that day".to_string(),
+        (0, config::EntryKind::Anniversary) => "happened that day".to_string(),
+        (age, config::EntryKind::Birthday) => format!("turning {}", age),
+        (years, config::EntryKind::Anniversary) => format_years(years),
+    })
+}
+
+/// Describe the current age of an entry, such as "45 years old" (or "6 months old" for babies),
+/// or None if the year of birth is unknown
+fn format_age_old(entry: &config::Entry) -> Option<String> {
+    let months = entry.age_in_months_now()?;
+    Some(match months {
+        ..0 => "not born yet".to_string(),
+        0..12 => format!("{} old", format_age_in_months(months)),
+        _ => format!("{} old", format_years(entry.age_now()?)),
+    })
+}
+
+/// Format an age in months as years and months, such as "1 year, 6 months"
+fn format_age_in_months(months: i32) -> String {
+    let plural = |n: i32, unit: &str| format!("{} {}{}", n, unit, if n == 1 { "" } else { "s" });
+    match (months / 12, months % 12) {
+        (0, months) => plural(months, "month"),
+        (years, 0) => plural(years, "year"),
+        (years, months) => format!("{}, {}", plural(years, "year"), plural(months, "month")),
+    }
+}
+
+/// Format the time of an occurence, in the local timezone or in UTC
+fn format_occurence(dt: DateTime<Local>, utc: bool) -> String {
+    if utc {
+        dt.with_timezone(&Utc)
+            .format("%Y-%m-%d %H:%M UTC")
+            .to_string()
+    } else {
+        dt.format("%Y-%m-%d %H:%M %:z").to_string()
+    }
+}
+
+/// Get the date of a command line option that requires the year to be specified.
+fn require_year(date: &config::BirthdayDate, option: &str) -> NaiveDate {
+    if date.year.is_none() {
+        exit_with_error(
+            ErrorCategory::Value,
+            format!("The year must be specified for the '{}' option.", option),
+        );
+    }
+    date.naive_date_safe_year()
+}
+
+/// The dates between which entries should happen, from the 'since' and 'until' options
+struct DateRange {
+    since: Option<NaiveDate>,
+    until: Option<NaiveDate>,
+}
+
+impl DateRange {
+    /// Validate the 'since' and 'until' options, exiting if they are invalid
+    fn new(since: Option<config::BirthdayDate>, until: Option<config::BirthdayDate>) -> Self {
+        let since = since.map(|since| require_year(&since, "since"));
+        let until = until.map(|until| require_year(&until, "until"));
+        if let (Some(since), Some(until)) = (since, until) {
+            if since > until {
+                exit_with_usage_error(
+                    ErrorKind::ValueValidation,
+                    "The 'since' date can't be after the 'until' date.",
+                );
+            }
+        }
+        Self { since, until }
+    }
+
+    /// Whether the next occurence of the entry (inclusively) is between the dates
+    fn contains(&self, entry: &config::Entry, now: DateTime<Local>) -> bool {
+        let date = entry.next_occurence.unwrap_or(now).date_naive();
+        self.since.is_none_or(|since| since <= date) && self.until.is_none_or(|until| date <= until)
+    }
+}
+
+/// Select the entries displayed by list among the given ones, sorted from the farthest to the closest:
+/// the closest --limit entries among those matching the filters, in the same order
+fn select_entries<'a>(
+    entries: &'a [config::Entry],
+    args: &cli::ListArgs,
+    now: DateTime<Local>,
+    before_date: Option<NaiveDate>,
+    date_range: &DateRange,
+) -> Vec<&'a config::Entry> {
+    let matching: Vec<&config::Entry> = entries
+        .iter()
+        // Only show entries that will happen strictly before before_date
+        .filter(|entry| {
+            before_date
+                .map(|before_date| entry.next_occurence.unwrap_or(now).date_naive() < before_date)
+                .unwrap_or(true)
+        })
+        // Only show entries that will happen between the 'since' and 'until' dates
+        .filter(|entry| date_range.contains(entry, now))
+        .filter(|entry| {
+            args.within
+                .is_none_or(|within| entry.num_days_until() <= within)
+        })
+        .filter(|entry| args.month.is_none_or(|month| entry.date.month == month))
+        .filter(|entry| match (args.timezone, entry.timezone) {
+            (None, _) => true,
+            (Some(_), None) => args.include_local,
+            (Some(timezone), Some(entry_timezone)) => timezone == entry_timezone,
+        })
+        .collect();
+    // The limit is applied last, so that it doesn't leave out entries matching the filters
+    let limit = args.limit.unwrap_or(matching.len());
+    matching[matching.len().saturating_sub(limit)..].to_vec()
+}
+
+/// Group the entries by the date of their next birthday, from the closest date to the farthest.
+/// The entries must be sorted from the farthest to the closest, like sort_entries does.
+fn agenda_dates(entries: &[config::Entry]) -> Vec<(NaiveDate, Vec<&config::Entry>)> {
+    // Entries in different timezones can start a bit apart, so the date is searched among all of them
+    let mut dates: Vec<(NaiveDate, Vec<&config::Entry>)> = vec![];
+    for entry in entries.iter().rev() {
+        let day = entry.next_date();
+        match dates.iter_mut().find(|(date, _)| *date == day) {
+            Some((_, date)) => date.push(entry),
+            None => dates.push((day, vec![entry])),
+        }
+    }
+    dates
+}
+
+/// Parse the ConfigEntry to Entry, exiting if any of them is invalid.
+/// The occurences are computed from the given date instead of the current one if there is one.
+fn parse_entries(
+    birthdays: Vec<config::ConfigEntry>,
+    today: Option<NaiveDate>,
+    leap_day: config::LeapDay,
+) -> Vec<config::Entry> {
+    match birthdays
+        .into_iter()
+        .map(|config_entry| config::Entry::from_config(config_entry, today, leap_day))
+        .collect()
+    {
+        Ok(entries) => entries,
+        Err(e) => match e {
+            config::EntryError::TimezoneParseError(e) => {
+                exit_with_error(
+                    ErrorCategory::Timezone,
+                    format!("Error parsing timezone: {}.", e),
+                );
+            }
+        },
+    }
+}
+
+/// The english name of a weekday, such as "Tuesday"
+fn weekday_name(weekday: Weekday) -> &'static str {
+    match weekday {
+        Weekday::Mon => "Monday",
+        Weekday::Tue => "Tuesday",
+        Weekday::Wed => "Wednesday",
+        Weekday::Thu => "Thursday",
+        Weekday::Fri => "Friday",
+        Weekday::Sat => "Saturday",
+        Weekday::Sun => "Sunday",
+    }
+}
+
+/// The ISO 8601 form of the date of an entry, using "--" instead of the year if it is unknown,
+/// and leaving out the day if only the month is known (such as "1990-12" or "--12")
+fn format_iso_date(entry: &config::Entry) -> String {
+    let year = match entry.date.year {
+        Some(year) => format!("{:04}", year),
+        None => "-".to_string(),
+    };
+    match entry.day_unknown {
+        true => format!("{}-{:02}", year, entry.date.month),
+        false => format!("{}-{:02}-{:02}", year, entry.date.month, entry.date.day),
+    }
+}
+
+/// The compact form of the date of an entry, such as "Dec 3rd", or "Dec" if the day is unknown
+fn format_compact_date(entry: &config::Entry) -> String {
+    let month = entry.date.naive_date_safe_year().format("%b");
+    match entry.day_unknown {
+        true => month.to_string(),
+        false => format!(
+            "{} {}{}",
+            month,
+            entry.date.day,
+            utils::ordinal_suffix(entry.date.day)
+        ),
+    }
+}
+
+/// Describe an entry in one line, such as "24 Dec — Alice (turning 35, in 3 days)"
+fn format_oneline(
+    entry: &config::Entry,
+    now: DateTime<Local>,
+    precision: config::Humanize,
+    half: bool,
+    date_format: cli::DateFormat,
+    separator: &str,
+    reverse_name: bool,
+) -> String {
+    let when = match entry.next_occurence {
+        _ if entry.day_unknown => "day unknown".to_string(),
+        Some(dt) => humanize_until(dt, now, precision).to_lowercase(),
+        None => "today".to_string(),
+    };
+    let details = match entry.next_age() {
+        Some(years) if entry.kind == config::EntryKind::Anniversary => {
+            format!("{}, {}", format_years(years), when)
+        }
+        Some(age) if half => format!("turning {}½, {}", age, when),
+        Some(age) => format!("turning {}, {}", age, when),
+        None => when,
+    };
+    let date = match (date_format, entry.day_unknown) {
+        (cli::DateFormat::Iso, _) => format_iso_date(entry),
+        (cli::DateFormat::Compact, _) => format_compact_date(entry),
+        (cli::DateFormat::Text, true) => entry.date.naive_date_safe_year().format("%b").to_string(),
+        (cli::DateFormat::Text, false) => entry
+            .date
+            .naive_date_safe_year()
+            .format("%d %b")
+            .to_string(),
+    };
+    let name = match reverse_name {
+        true => entry.reversed_name(),
+        false => entry.name.clone(),
+    };
+    format!("{}{}{} ({})", date, separator, name, details)
+}
+
+/// Describe an entry as a JSON object on a single line, such as
+/// {"name": "Alice", "date": "1990-12-24", "kind": "birthday", "next": "2024-12-24", "days": 3, "age": 34}.
+/// The next date is null for entries without a day, and the age is null if the year is unknown.
+/// This is the object of every JSON output of entries (list --json-lines and the entries of list --json),
+/// so other JSON outputs of entries should reuse it.
+fn format_json_line(entry: &config::Entry) -> String {
+    let days = entry.num_days_until();
+    let next = match entry.day_unknown {
+        true => "null".to_string(),
+        false => utils::json_string(&entry.next_date().format("%Y-%m-%d").to_string()),
+    };
+    let age = match entry.next_age() {
+        Some(age) => age.to_string(),
+        None => "null".to_string(),
+    };
+    let kind = match entry.kind {
+        config::EntryKind::Birthday => "birthday",
+        config::EntryKind::Anniversary => "anniversary",
+    };
+    format!(
+        "{{\"name\": {}, \"date\": {}, \"kind\": {}, \"next\": {}, \"days\": {}, \"age\": {}}}",
+        utils::json_string(&entry.name),
+        utils::json_string(&format_iso_date(entry)),
+        utils::json_string(kind),
+        next,
+        days,
+        age
+    )
+}
+
+/// Describe the shown entries as a JSON object on a single line, with the total number of entries
+/// to know if some were left out: {"total": 12, "shown": 2, "entries": [...]}
+fn format_json_list(shown: &[&config::Entry], total: usize) -> String {
+    let entries: Vec<String> = shown.iter().map(|entry| format_json_line(entry)).collect();
+    format!(
+        "{{\"total\": {}, \"shown\": {}, \"entries\": [{}]}}",
+        total,
+        shown.len(),
+        entries.join(", ")
+    )
+}
+
+/// The number of characters after which budgets are wrapped in wide tables
+const WIDE_BUDGET_WIDTH: usize = 20;
+
+/// The prettytable format of a table style
+fn table_format(style: config::TableStyle) -> format::TableFormat {
+    match style {
+        config::TableStyle::Rounded => format::FormatBuilder::new()
+            .column_separator('│')
+            .borders('│')
+            .separators(
+                &[format::LinePosition::Top],
+                format::LineSeparator::new('─', '┬', '╭', '╮'),
+            )
+            .separators(
+                &[format::LinePosition::Intern],
+                format::LineSeparator::new('─', '┼', '├', '┤'),
+            )
+            .separators(
+                &[format::LinePosition::Bottom],
+                format::LineSeparator::new('─', '┴', '╰', '╯'),
+            )
+            .padding(1, 1)
+            .build(),
+        config::TableStyle::Box => *format::consts::FORMAT_BOX_CHARS,
+        config::TableStyle::Ascii => *format::consts::FORMAT_DEFAULT,
+        config::TableStyle::Compact => *format::consts::FORMAT_NO_LINESEP_WITH_TITLE,
+        config::TableStyle::Clean => *format::consts::FORMAT_CLEAN,
+        config::TableStyle::Borderless => *format::consts::FORMAT_NO_BORDER,
+    }
+}
+
+/// Display the given entries as a table, filtered according to the list arguments.
+/// Returns whether any entry was displayed.
+fn list(config: config::Config, args: &cli::ListArgs) -> bool {
+    let birthdays = config.birthdays;
+    let leap_day = config.options.leap_day;
+    let date_format = match args.compact_date {
+        true => cli::DateFormat::Compact,
+        false => args.format,
+    };
+    if birthdays.is_empty() {
+        eprintln!("No entries found, add some with the 'add' command.");
+        return false;
+    }
+
+    // Pretend that it is currently the 'relative_to' date, at the current time
+    let relative_to: Option<NaiveDate> = args
+        .relative_to
+        .map(|relative_to| require_year(&relative_to, "relative-to"));
+    let now: DateTime<Local> = match relative_to {
+        Some(date) => utils::localize(&Local, date.and_time(utils::now().time())),
+        None => utils::now(),
+    };
+
+    // Validate the 'before' date
+    let before_date: Option<NaiveDate> = args.before.map(|before| require_year(&before, "before"));
+    // Check that the date isn't in the past
+    if let Some(before_date) = before_date {
+        if before_date < now.date_naive() {
+            exit_with_usage_error(
+                ErrorKind::ValueValidation,
+                "The 'before' date can't be in the past.",
+            );
+        }
+    };
+
+    let date_range = DateRange::new(args.since, args.until);
+
+    let mut entries = parse_entries(
+        birthdays
+            .into_iter()
+            .filter(|config_entry| args.all || !config_entry.hidden)
+            .filter_map(|mut config_entry| {
+                if args.half {
+                    config_entry.date = config_entry.date.half_birthday()?;
+                }
+                Some(config_entry)
+            })
+            .collect(),
+        relative_to,
+        leap_day,
+    );
+
+    // Sort the entries by date of next occurence
+    // TODO: Maybe move this earlier to we don't have to use mut on entries
+    config::sort_entries(&mut entries);
+
+    let columns: Vec<cli::Column> = match &args.columns {
+        Some(columns) => columns.clone(),
+        None => {
+            let mut columns = vec![
+                cli::Column::Index,
+                cli::Column::Name,
+                cli::Column::Date,
+                cli::Column::Age,
+                cli::Column::In,
+            ];
+            if args.budget || args.wide {
+                columns.push(cli::Column::Budget);
+            }
+            if args.birthstone || args.wide {
+                columns.push(cli::Column::Stone);
+            }
+            if args.born || args.wide {
+                columns.push(cli::Column::Born);
+            }
+            if args.show_timezone || args.wide {
+                columns.push(cli::Column::Timezone);
+            }
+            if args.calendar_display.is_some() {
+                columns.push(cli::Column::Calendar);
+            }
+            columns
+        }
+    };
+
+    let shown: Vec<&config::Entry> = select_entries(&entries, args, now, before_date, &date_range);
+
+    // Keep only the closest or farthest birthday
+    let by_date =
+        |entry: &&config::Entry| (entry.next_occurence, utils::collation_key(&entry.name));
+    let shown: Vec<&config::Entry> = match (args.first, args.last) {
+        (true, _) => shown.into_iter().min_by_key(by_date).into_iter().collect(),
+        (_, true) => shown.into_iter().max_by_key(by_date).into_iter().collect(),
+        _ => shown,
+    };
+
+    // Sort by date first, so entries that are equal for the other sort keys stay sorted by date
+    let descending = args.sort_dir == cli::SortDir::Desc;
+    let mut shown = shown;
+    if descending {
+        shown.sort_by_cached_key(|entry| {
+            (
+                Reverse(entry.next_occurence),
+                utils::collation_key(&entry.name),
+            )
+        });
+    } else {
+        shown.sort_by_cached_key(|entry| (entry.next_occurence, utils::collation_key(&entry.name)));
+    }
+
+    let shown: Vec<&config::Entry> = match args.sort {
+        cli::SortBy::Date if args.month.is_some() => {
+            config::sort_entries_by_day(&mut shown, descending);
+            shown
+        }
+        cli::SortBy::Date => shown,
+        cli::SortBy::Days => {
+            if descending {
+                shown.sort_by_key(|entry| Reverse(entry.num_days_until()));
+            } else {
+                shown.sort_by_key(|entry| entry.num_days_until());
+            }
+            shown
+        }
+        cli::SortBy::Month => {
+            config::sort_entries_by_calendar(&mut shown, descending);
+            shown
+        }
+        cli::SortBy::Name => {
+            if descending {
+                shown.sort_by_cached_key(|entry| {
+                    Reverse(utils::collation_key(&entry.reversed_name()))
+                });
+            } else {
+                shown.sort_by_cached_key(|entry| utils::collation_key(&entry.reversed_name()));
+            }
+            shown
+        }
+        cli::SortBy::Age => {
+            let (mut known, unknown): (Vec<&config::Entry>, Vec<&config::Entry>) = shown
+                .into_iter()
+                .partition(|entry| entry.next_age().is_some());
+            if descending {
+                known.sort_by_key(|entry| Reverse(entry.next_age()));
+            } else {
+                known.sort_by_key(|entry| entry.next_age());
+            }
+            match args.unknown_age {
+                cli::UnknownAge::First => unknown.into_iter().chain(known).collect(),
+                cli::UnknownAge::Last => known.into_iter().chain(unknown).collect(),
+                cli::UnknownAge::Hidden => known,
+            }
+        }
+    };
+
+    if shown.is_empty() {
+        eprintln!("No entries match the given criteria.");
+        return false;
+    }
+
+    if args.porcelain {
+        // Entries without a day have no date to print
+        for entry in shown.iter().filter(|entry| !entry.day_unknown) {
+            println!(
+                "{}\t{}\t{}",
+                entry.name,
+                entry.next_date().format("%Y-%m-%d"),
+                entry.num_days_until()
+            );
+        }
+        return true;
+    }
+
+    if args.json_lines {
+        for entry in &shown {
+            println!("{}", format_json_line(entry));
+        }
+        return true;
+    }
+
+    if args.json {
+        println!("{}", format_json_list(&shown, entries.len()));
+        return true;
+    }
+
+    let is_milestone = |entry: &config::Entry| {
+        args.milestones
+            && entry
+                .next_age()
+                .is_some_and(|age| config.options.is_milestone(age))
+    };
+    let is_highlighted = |entry: &config::Entry| {
+        is_milestone(entry)
+            || args
+                .highlight
+                .iter()
+                .any(|name| utils::names_match(&entry.name, name))
+    };
+
+    let make_table = |entries: &[&config::Entry]| {
+        let mut table = Table::new();
+        table.set_format(table_format(
+            args.table_style.unwrap_or(config.display.table_style),
+        ));
+
+        // Makes the header bold
+        if !args.no_header {
+            table.set_titles(Row::new(
+                columns
+                    .iter()
+                    .map(|column| match (column, args.calendar_display) {
+                        (cli::Column::Calendar, Some(calendar)) => cell!(b -> calendar.name()),
+                        _ => cell!(b -> column.title()),
+                    })
+                    .collect(),
+            ));
+        }
+
+        // Pad the ages so the arrows are aligned
+        let age_width = entries
+            .iter()
+            .filter_map(|entry| entry.next_age())
+            .flat_map(|age| [age - 1, age])
+            .map(|age| age.to_string().len())
+            .max()
+            .unwrap_or(0);
+
+        for (index, entry) in entries.iter().enumerate() {
+            let row = columns
+                .iter()
+                .map(|column| match column {
+                    cli::Column::Index => cell!(index + 1),
+                    cli::Column::Name => match args.reverse_name {
+                        true => cell!(entry.reversed_name()),
+                        false => cell!(entry.name),
+                    },
+                    // Chrono doesn't support locales yet
+                    // entry.date.format("%C").to_string(),
+                    cli::Column::Date => {
+                        let date = match entry.day_unknown {
+                            _ if date_format == cli::DateFormat::Iso => format_iso_date(entry),
+                            _ if date_format == cli::DateFormat::Compact => {
+                                format_compact_date(entry)
+                            }
+                            true => format!(
+                                "{} (day unknown)",
+                                entry.date.naive_date_safe_year().format("%B")
+                            ),
+                            false => entry
+                                .date
+                                .naive_date_safe_year()
+                                .format("%d %B")
+                                .to_string(),
+                        };
+                        match args.festive {
+                            true => {
+                                cell!(format!("{} {}", utils::month_emoji(entry.date.month), date))
+                            }
+                            false => cell!(date),
+                        }
+                    }
+                    cli::Column::Age => {
+                        cell!(match (entry.next_age(), entry.age_in_months_now()) {
+                            // Anniversaries display the number of years they will celebrate
+                            (Some(years), _) if entry.kind == config::EntryKind::Anniversary => {
+                                format_years(years)
+                            }
+                            (Some(_), _) if args.relative => {
+                                format_age_old(entry).unwrap_or_default()
+                            }
+                            (Some(_), Some(months))
+                                if !args.half
+                                    && args
+                                        .precise_age
+                                        .is_some_and(|years| months < years * 12) =>
+                            {
+                                format_age_in_months(months)
+                            }
+                            // The year of half birthdays is shifted, so they are half a year older than that
+                            (Some(age), _) if args.half => format!(
+                                "{:>width$} 🡒 {:<width$}",
+                                format!("{}½", age - 1),
+                                format!("{}½", age),
+                                width = age_width + 1
+                            ),
+                            (Some(age), _) =>
+                                format!("{:>width$} 🡒 {:<width$}", age - 1, age, width = age_width),
+                            (None, _) => "?".to_string(),
+                        })
+                    }
+                    cli::Column::In => cell!(match entry.next_occurence {
+                        // There is no countdown to a day that isn't known
+                        _ if entry.day_unknown => "".to_string(),
+                        Some(dt) => humanize_until(dt, now, config.display.humanize),
+                        None => "Today!".to_string(),
+                    }),
+                    cli::Column::Budget => cell!(match (&entry.budget, args.wide) {
+                        // Wide tables are less likely to fit in the terminal
+                        (Some(budget), true) => utils::wrap_text(budget, WIDE_BUDGET_WIDTH),
+                        (Some(budget), false) => budget.clone(),
+                        (None, _) => "".to_string(),
+                    }),
+                    cli::Column::Stone => cell!(utils::birthstone(entry.date.month)),
+                    cli::Column::Calendar => {
+                        cell!(match (args.calendar_display, entry.date.year) {
+                            (Some(calendar), Some(_)) if !entry.day_unknown => {
+                                calendar.format(entry.date.naive_date_safe_year())
+                            }
+                            _ => "".to_string(),
+                        })
+                    }
+                    cli::Column::Born => cell!(match entry.day_unknown {
+                        true => "",
+                        false => entry.date.birth_weekday().map(weekday_name).unwrap_or(""),
+                    }),
+                    cli::Column::Timezone => cell!(match entry.timezone {
+                        Some(timezone) => timezone.to_string(),
+                        None => "local".to_string(),
+                    }),
+                })
+                .collect::<Vec<Cell>>();
+            if is_highlighted(entry) {
+                table.add_row(Row::new(
+                    row.into_iter().map(|cell| cell.style_spec("bFy")).collect(),
+                ));
+            } else {
+                table.add_row(Row::new(row));
+            }
+        }
+        table
+    };
+
+    let print_entries = |entries: &[&config::Entry]| {
+        if args.oneline {
+            for entry in entries {
+                let line = format_oneline(
+                    entry,
+                    now,
+                    config.display.humanize,
+                    args.half,
+                    date_format,
+                    &args.separator,
+                    args.reverse_name,
+                );
+                // Same style as the highlighted rows of the table, only when it can be displayed
+                if is_highlighted(entry) && io::stdout().is_terminal() {
+                    println!("\x1B[1;33m{}\x1B[0m", line);
+                } else {
+                    println!("{}", line);
+                }
+            }
+        } else {
+            make_table(entries).printstd();
+        }
+    };
+
+    match args.group_by {
+        None => print_entries(&shown),
+        Some(cli::GroupBy::Relationship) => {
+            let mut groups: BTreeMap<&str, Vec<&config::Entry>> = BTreeMap::new();
+            let mut without_group: Vec<&config::Entry> = vec![];
+            for entry in shown.iter() {
+                match &entry.relationship {
+                    Some(relationship) => groups.entry(relationship).or_default().push(entry),
+                    None => without_group.push(entry),
+                }
+            }
+            for (relationship, group) in groups.iter() {
+                println!("{}:", relationship);
+                print_entries(group);
+            }
+            if !without_group.is_empty() {
+                println!("No relationship:");
+                print_entries(&without_group);
+            }
+        }
+        Some(cli::GroupBy::Season) => {
+            // The seasons are in the order their first entry is displayed
+            let mut groups: Vec<(utils::Season, Vec<&config::Entry>)> = vec![];
+            for entry in shown.iter() {
+                let season = utils::season_of(entry.date.month, config.display.hemisphere);
+                match groups
+                    .iter_mut()
+                    .find(|(group_season, _)| *group_season == season)
+                {
+                    Some((_, group)) => group.push(entry),
+                    None => groups.push((season, vec![entry])),
+                }
+            }
+            for (season, group) in groups.iter() {
+                println!("{}:", season.name());
+                print_entries(group);
+            }
+        }
+    }
+
+    if args.summary && shown.len() < entries.len() {
+        println!("Showing {} of {} entries", shown.len(), entries.len());
+    }
+    true
+}
+
+/// The name and the date of birth of the entry with the given name or alias, for the gap command,
+/// exiting if there is not exactly one such entry or if its year of birth is unknown
+fn gap_birth_date(birthdays: &[config::ConfigEntry], name: &str) -> (String, NaiveDate) {
+    let matches: Vec<&config::ConfigEntry> = birthdays
+        .iter()
+        .filter(|config_entry| config_entry.has_name(name))
+        .collect();
+    match matches[..] {
+        [] => exit_with_error(
+            ErrorCategory::Value,
+            format!("No entry named '{}' was found.", name),
+        ),
+        [config_entry] => match config_entry.date {
+            config::EntryDate::Date(date) if date.year.is_some() => {
+                (config_entry.name.clone(), date.naive_date_safe_year())
+            }
+            _ => exit_with_error(
+                ErrorCategory::Value,
+                format!(
+                    "The full date of birth of {}, with the year, is needed to compute the gap.",
+                    config_entry.name
+                ),
+            ),
+        },
+        _ => exit_with_error(
+            ErrorCategory::Value,
+            format!("{} entries are named '{}'.", matches.len(), name),
+        ),
+    }
+}
+
+/// Exit with code 1 if nothing was displayed and --strict is used
+fn exit_if_nothing_shown(shown: bool, strict: bool) {
+    if !shown && strict {
+        exit(1);
+    }
+}
+
+/// Print the entries as CSV.
+/// Weekday dates are exported as the date of their closest occurence,
+/// and dates without a day are exported without a day (and without a birthday for Google Contacts).
+fn export_csv(entries: &[config::Entry], schema: cli::CsvSchema, separator: u8) -> csv::Result<()> {
+    let mut writer = csv::WriterBuilder::new()
+        .delimiter(separator)
+        .from_writer(io::stdout());
+    match schema {
+        cli::CsvSchema::Generic => {
+            writer.write_record(["name", "day", "month", "year", "timezone"])?;
+            for entry in entries {
+                writer.write_record([
+                    entry.name.clone(),
+                    match entry.day_unknown {
+                        true => "".to_string(),
+                        false => entry.date.day.to_string(),
+                    },
+                    entry.date.month.to_string(),
+                    entry
+                        .date
+                        .year
+                        .map(|year| year.to_string())
+                        .unwrap_or_default(),
+                    entry.timezone.map(|tz| tz.to_string()).unwrap_or_default(),
+                ])?;
+            }
+        }
+        cli::CsvSchema::Google => {
+            writer.write_record(["Name", "Birthday"])?;
+            for entry in entries {
+                let year = entry.date.year.map(|year| format!("{:04}", year));
+                writer.write_record([
+                    entry.name.clone(),
+                    match entry.day_unknown {
+                        true => "".to_string(),
+                        false => format!(
+                            "{}-{:02}-{:02}",
+                            year.unwrap_or("-".to_string()),
+                            entry.date.month,
+                            entry.date.day
+                        ),
+                    },
+                ])?;
+            }
+        }
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Ask for a value on stdin until a valid one is given
+fn prompt<T>(question: &str, parse: impl Fn(&str) -> Result<T, String>) -> T {
+    loop {
+        print!("{}: ", question);
+        let _ = io::stdout().flush();
+        let mut answer = String::new();
+        match io::stdin().read_line(&mut answer) {
+            // End of input
+            Ok(0) => exit_with_error(ErrorCategory::Io, "No answer was given."),
+            Ok(_) => match parse(answer.trim()) {
+                Ok(value) => return value,
+                Err(e) => eprintln!("{}", e),
+            },
+            Err(e) => exit_with_error(
+                ErrorCategory::Io,
+                format!("Error reading the answer: {}", e),
+            ),
+        }
+    }
+}
+
+/// Ask for the name and date of the add command if they are missing, and for its timezone as well.
+/// When stdin isn't a terminal, exits with the usual error about missing arguments instead.
+fn complete_add_args(command: &mut cli::Commands) {
+    let cli::Commands::Add {
+        name_positional,
+        date_positional,
+        name,
+        date,
+        timezone,
+        ..
+    } = command
+    else {
+        return;
+    };
+    let name_missing = name.is_none() && name_positional.is_none();
+    let date_missing = date.is_none() && date_positional.is_none();
+    if !name_missing && !date_missing {
+        return;
+    }
+
+    if !io::stdin().is_terminal() {
+        let missing: Vec<&str> = [(name_missing, "<NAME>"), (date_missing, "<DATE>")]
+            .into_iter()
+            .filter_map(|(missing, arg)| missing.then_some(arg))
+            .collect();
+        exit_with_usage_error(
+            ErrorKind::MissingRequiredArgument,
+            format!(
+                "the following required arguments were not provided: {}",
+                missing.join(" ")
+            ),
+        );
+    }
+
+    if name_missing {
+        *name = Some(prompt("Name", |answer| match answer {
+            "" => Err("The name can't be empty.".to_string()),
+            answer => Ok(answer.to_string()),
+        }));
+    }
+    if date_missing {
+        *date = Some(prompt("Date (DD/MM or DD/MM/YYYY)", |answer| {
+            answer.parse().map_err(|e: &str| format!("{}.", e))
+        }));
+    }
+    if timezone.is_none() {
+        *timezone = prompt(
+            "Timezone (optional, such as Europe/Paris or +02:00)",
+            |answer| match answer {
+                "" => Ok(None),
+                answer => answer.parse().map(Some),
+            },
+        );
+    }
+}
+
+/// The path of a birthday file for error messages, since "-" reads it from stdin
+fn describe_config_path(path: &Path) -> String {
+    match config::is_stdin(path) {
+        true => "the birthday file from stdin".to_string(),
+        false => path.display().to_string(),
+    }
+}
+
+/// Load a birthday file, using a default one if it doesn't exist and exiting if it is invalid
+fn load_config_file(conf_path: PathBuf) -> config::ConfigFile {
+    match config::load_config(&conf_path) {
+        Ok(cfg) => cfg,
+        Err(e) => match e {
+            // Use a default config if no config file is found
+            config::LoadConfigError::ConfigNotFound => config::ConfigFile::new(conf_path),
+            config::LoadConfigError::IoError(path, e) => {
+                exit_with_error(
+                    ErrorCategory::Config,
+                    format!("Error reading {}: {}", describe_config_path(&path), e),
+                );
+            }
+            config::LoadConfigError::TomlError(path, e) => {
+                exit_with_error(ErrorCategory::Config, format!("Error parsing {}:\n{}\nYou can delete the file, it will be recreated the next time you add a new birthday.", describe_config_path(&path), e));
+            }
+            config::LoadConfigError::NewerVersion(version) => {
+                exit_with_error(
+                    ErrorCategory::Config,
+                    format!(
+                        "The birthday file uses version {} of the format, but this version of bday only supports up to version {}.\nUpdate bday to use this file.",
+                        version,
+                        config::CONFIG_VERSION
+                    ),
+                );
+            }
+        },
+    }
+}
+
 /// Exit codes:  
 /// 0: Success. Note that this is still returned if no entries are found, but
 ///    the program will print an error message to stderr in that case, leaving stdout empty.  
+/// 1: No entries were found, only when using --strict  
 /// 2: Invalid command, or other clap parsing error  
-/// 3: Error reading or parsing the config file  
+/// 3: Error reading or parsing the config file, trying to modify a read-only one,
+///    or no entry with the given name  
 fn main() {
-    let cli = cli::Cli::parse();
+    let mut cli = match cli::Cli::try_parse() {
+        Ok(cli) => cli,
+        // The arguments couldn't be parsed, so the error format is looked for by hand
+        Err(e) if e.use_stderr() && cli::json_errors_requested(std::env::args()) => {
+            // Only the first line, without the usage section
+            let message = e.to_string();
+            let message = message.lines().next().unwrap_or_default();
+            print_json_error(ErrorCategory::Usage, message.trim_start_matches("error: "));
+            exit(2);
+        }
+        Err(e) => e.exit(),
+    };
+    let _ = ERROR_FORMAT.set(cli.error_format);
+    if let Some(now) = cli.now {
+        utils::set_now(now);
+    }
 
-    //? Defaults to $XDG_CONFIG_HOME/bday.toml
-    let conf_path: PathBuf = cli.file.unwrap_or_else(|| {
-        BaseDirs::new()
+    //? Defaults to $BDAY_FILE, then $XDG_CONFIG_HOME/bday.toml
+    let conf_paths: Vec<PathBuf> = if cli.file.is_empty() {
+        vec![BaseDirs::new()
             .map(|p| p.config_dir().join("bday.toml"))
-            .expect("Error getting the default birthday file path.\nYou can always use a custom birthday file with the --file option.")
-    });
+            .expect("Error getting the default birthday file path.\nYou can always use a custom birthday file with the --file option.")]
+    } else {
+        cli.file
+    };
 
-    let mut conf_file: config::ConfigFile = match config::load_config(&conf_path) {
-        Ok(cfg) => cfg,
-        Err(e) => match e {
-            // Use a default config if no config file is found
-            config::LoadConfigError::ConfigNotFound => config::ConfigFile {
-                path: conf_path,
-                config: config::Config::default(),
-            },
-            config::LoadConfigError::IoError(e) => {
-                let _ = cli::Cli::command()
-                    .error(ErrorKind::Io, format!("Error reading config file: {}", e))
-                    // TODO: remove the "usage: " section that gets displayed
-                    .print();
-                exit(3);
-            }
-            config::LoadConfigError::TomlError(e) => {
-                let _ = cli::Cli::command()
-                    .error(ErrorKind::Io, format!("Error parsing the birthday file:\n{}\nYou can delete the file, it will be recreated the next time you add a new birthday.", e))
-                    // TODO: remove the "usage: " section that gets displayed
-                    .print();
-                exit(3);
+    if let cli::Commands::Where = cli.command {
+        for conf_path in conf_paths.iter() {
+            println!("{}", conf_path.display());
+        }
+        return;
+    }
+
+    if conf_paths.len() > 1 && cli.command.is_mutating() {
+        exit_with_usage_error(
+            ErrorKind::ArgumentConflict,
+            "This command modifies the birthday file, so only one can be given.",
+        );
+    }
+
+    if config::is_stdin(&conf_paths[0]) && cli.command.is_mutating() {
+        exit_with_usage_error(
+            ErrorKind::ArgumentConflict,
+            "This command modifies the birthday file, so it can't be read from stdin.",
+        );
+    }
+
+    complete_add_args(&mut cli.command);
+
+    // Held until the program exits, so the file can't change between reading and writing it
+    let _lock = if cli.command.is_mutating() {
+        // The directory of the default birthday file may not exist yet on a fresh system
+        if let Some(dir) = conf_paths[0]
+            .parent()
+            .filter(|dir| !dir.as_os_str().is_empty())
+        {
+            if let Err(e) = fs::create_dir_all(dir) {
+                exit_with_error(
+                    ErrorCategory::Io,
+                    format!(
+                        "Error creating the directory of the birthday file ({}): {}",
+                        dir.display(),
+                        e
+                    ),
+                );
             }
-        },
+        }
+        match config::lock_config(&conf_paths[0]) {
+            Ok(lock) => Some(lock),
+            Err(config::LockConfigError::IoError(e)) => {
+                exit_with_error(
+                    ErrorCategory::Io,
+                    format!("Error locking the birthday file: {}", e),
+                );
+            }
+            Err(config::LockConfigError::Locked) => {
+                exit_with_error(
+                    ErrorCategory::Io,
+                    "The birthday file is being modified by another process, try again later.",
+                );
+            }
+        }
+    } else {
+        None
     };
 
+    // The entries of the other files are added to the first one, which is used for everything else
+    let mut conf_paths = conf_paths.into_iter();
+    let mut conf_file = load_config_file(conf_paths.next().unwrap());
+    for conf_path in conf_paths {
+        let other = load_config_file(conf_path);
+        conf_file.config.birthdays.extend(other.config.birthdays);
+    }
+    let leap_day = conf_file.config.options.leap_day;
+
+    if conf_file.config.meta.readonly && cli.command.is_mutating() {
+        exit_with_error(
+            ErrorCategory::Config,
+            format!(
+                "The birthday file {} is read-only, as set by its 'meta.readonly' key.",
+                conf_file.path.display()
+            ),
+        );
+    }
+
     match &cli.command {
         cli::Commands::Add {
+            name_positional,
+            date_positional,
             name,
             date,
+            allow_future,
             timezone,
+            hidden,
+            aliases,
+            first_name,
+            last_name,
+            budget,
+            photo,
+            email,
+            relationship,
+            kind,
+            time,
         } => {
+            // Missing arguments were asked for before loading the birthday file
+            let name = name.as_ref().or(name_positional.as_ref()).unwrap();
+            let date = date.as_ref().or(date_positional.as_ref()).unwrap();
+
+            if !allow_future && date.has_future_year(utils::now().date_naive()) {
+                exit_with_usage_error(
+                    ErrorKind::ValueValidation,
+                    "The year of birth is in the future, use --allow-future if this is intended.",
+                );
+            }
+
             // Add the entry to the config file
             let new_entry = config::ConfigEntry {
                 name: name.clone(),
-                date: *date,
-                timezone: timezone.as_ref().map(|tz| tz.name().to_string()),
+                date: config::EntryDate::Date(*date),
+                timezone: timezone.as_ref().map(|tz| tz.to_string()),
+                hidden: *hidden,
+                aliases: aliases.clone(),
+                first_name: first_name.clone(),
+                last_name: last_name.clone(),
+                budget: budget.clone(),
+                photo: photo.clone(),
+                email: email.clone(),
+                relationship: relationship.clone(),
+                kind: *kind,
+                time: *time,
+                source: None,
             };
-            conf_file.config.birthdays.push(new_entry);
-            let toml_str =
-                toml::to_string(&conf_file.config).expect("Error serializing birthday file");
-            fs::write(conf_file.path, toml_str).expect("Error writing birthday file");
+            conf_file.add_entry(new_entry);
+            write_config(&conf_file);
             println!(
-                "Added entry for {}, born: {}{}",
+                "Added entry for {}, {}: {}{}",
                 name,
+                match kind {
+                    config::EntryKind::Birthday => "born",
+                    config::EntryKind::Anniversary => "since",
+                },
                 date,
                 match timezone {
-                    Some(tz) => format!(" (Timezone: {})", tz.name()),
+                    Some(tz) => format!(" (Timezone: {})", tz),
                     None => "".to_string(),
                 }
             );
         }
-        cli::Commands::List { limit, before } => {
-            if conf_file.config.birthdays.is_empty() {
+        cli::Commands::List(args) => {
+            exit_if_nothing_shown(list(conf_file.config, args), cli.strict)
+        }
+        cli::Commands::Demo {
+            count,
+            seed,
+            list: args,
+        } => exit_if_nothing_shown(
+            list(
+                config::Config {
+                    birthdays: demo::generate_entries(*count, *seed),
+                    ..Default::default()
+                },
+                args,
+            ),
+            cli.strict,
+        ),
+        cli::Commands::Watch {
+            interval,
+            list: args,
+        } => loop {
+            // Clear the screen and move the cursor to the top left
+            print!("\x1B[2J\x1B[H");
+            list(conf_file.config.clone(), args);
+            thread::sleep(Duration::from_secs(*interval));
+        },
+        cli::Commands::Greeting { open, copy } => {
+            let entries = parse_entries(
+                conf_file
+                    .config
+                    .birthdays
+                    .iter()
+                    .filter(|config_entry| !config_entry.hidden)
+                    .cloned()
+                    .collect(),
+                None,
+                leap_day,
+            );
+            // Entries without a next occurence are happening today
+            let today: Vec<&config::Entry> = entries
+                .iter()
+                .filter(|entry| entry.next_occurence.is_none())
+                .collect();
+            if today.is_empty() {
+                eprintln!("No birthdays today.");
+                exit_if_nothing_shown(false, cli.strict);
+            }
+            let greetings: Vec<String> = today
+                .iter()
+                .map(|entry| conf_file.config.greeting(entry))
+                .collect();
+            let copied = *copy && !greetings.is_empty() && copy_to_clipboard(&greetings.join("\n"));
+            if copied {
+                eprintln!("Copied the greetings to the clipboard.");
+            } else {
+                if *copy && !greetings.is_empty() {
+                    eprintln!(
+                        "Warning: no clipboard is available, printing the greetings instead."
+                    );
+                }
+                for greeting in greetings.iter() {
+                    println!("{}", greeting);
+                }
+            }
+            for (entry, greeting) in today.iter().zip(greetings.iter()) {
+                if let (true, Some(email)) = (open, &entry.email) {
+                    open_link(&mailto_link(email, Some(greeting)));
+                }
+            }
+        }
+        cli::Commands::Agenda { count } => {
+            let mut entries = parse_entries(
+                conf_file
+                    .config
+                    .birthdays
+                    .into_iter()
+                    .filter(|config_entry| !config_entry.hidden)
+                    // The agenda needs to know the day of the dates
+                    .filter(|config_entry| {
+                        !matches!(config_entry.date, config::EntryDate::Month(_))
+                    })
+                    .collect(),
+                None,
+                leap_day,
+            );
+            config::sort_entries(&mut entries);
+            let now = utils::now();
+
+            let dates = agenda_dates(&entries);
+
+            if dates.is_empty() {
                 eprintln!("No entries found, add some with the 'add' command.");
-                exit(0);
+                exit_if_nothing_shown(false, cli.strict);
+            }
+            for (day, date) in dates.iter().take(*count) {
+                println!(
+                    "{} ({})",
+                    day.format("%d %B"),
+                    match date[0].next_occurence {
+                        Some(dt) => humanize_until(dt, now, conf_file.config.display.humanize),
+                        None => "Today".to_string(),
+                    }
+                );
+                let mut names: Vec<&str> = date.iter().map(|entry| entry.name.as_str()).collect();
+                names.sort_by_cached_key(|name| utils::collation_key(name));
+                for name in names {
+                    println!("  {}", name);
+                }
             }
+        }
+        cli::Commands::Milestones { within } => {
+            let today = utils::now().date_naive();
+            let mut milestones: Vec<(NaiveDate, i64, &str, config::EntryKind)> = conf_file
+                .config
+                .birthdays
+                .iter()
+                .filter(|config_entry| !config_entry.hidden)
+                .filter_map(|config_entry| match config_entry.date {
+                    config::EntryDate::Date(date) if date.year.is_some() => {
+                        let birth = date.naive_date_safe_year();
+                        let milestone =
+                            utils::next_celebrated_milestone((today - birth).num_days());
+                        let milestone_date = birth + TimeDelta::days(milestone);
+                        Some((
+                            milestone_date,
+                            milestone,
+                            config_entry.name.as_str(),
+                            config_entry.kind,
+                        ))
+                    }
+                    // The full date of birth is needed to count the days
+                    _ => None,
+                })
+                .filter(|(date, _, _, _)| (*date - today).num_days() <= *within)
+                .collect();
+            milestones.sort_by_cached_key(|(date, _, name, _)| (*date, utils::collation_key(name)));
 
-            let now: DateTime<Local> = Local::now();
+            if milestones.is_empty() {
+                eprintln!("No milestones in the next {} days.", within);
+                exit_if_nothing_shown(false, cli.strict);
+            }
+            for (date, milestone, name, kind) in milestones {
+                let days = (date - today).num_days();
+                println!(
+                    "{} ({})",
+                    date.format("%d %B %Y"),
+                    match utils::relative_day(days) {
+                        Some(day) => day.to_string(),
+                        None => format!("in {} days", days),
+                    }
+                );
+                match kind {
+                    config::EntryKind::Birthday => println!("  {}: {} days alive", name, milestone),
+                    config::EntryKind::Anniversary => println!("  {}: {} days", name, milestone),
+                }
+            }
+        }
+        cli::Commands::Stats { json, all } => {
+            let entries = parse_entries(
+                conf_file
+                    .config
+                    .birthdays
+                    .into_iter()
+                    .filter(|config_entry| *all || !config_entry.hidden)
+                    .collect(),
+                None,
+                leap_day,
+            );
+            if entries.is_empty() {
+                eprintln!("No entries found, add some with the 'add' command.");
+                exit_if_nothing_shown(false, cli.strict);
+            }
+            let stats = stats::Stats::new(&entries);
+            if *json {
+                println!("{}", stats.to_json());
+            } else {
+                let style = table_format(conf_file.config.display.table_style);
+                let mut table = Table::new();
+                table.set_format(style);
+                table.add_row(Row::new(vec![cell!("Entries"), cell!(stats.total)]));
+                table.add_row(Row::new(vec![
+                    cell!("With a year of birth"),
+                    cell!(stats.with_year),
+                ]));
+                table.add_row(Row::new(vec![
+                    cell!("Average age"),
+                    cell!(match stats.average_age {
+                        Some(average_age) => average_age.to_string(),
+                        None => "?".to_string(),
+                    }),
+                ]));
+                table.add_row(Row::new(vec![
+                    cell!("Oldest"),
+                    cell!(match stats.oldest {
+                        Some(entry) => format!("{} ({})", entry.name, entry.age_now().unwrap()),
+                        None => "?".to_string(),
+                    }),
+                ]));
+                table.printstd();
 
-            // Validate the 'before' date
-            let before_date: Option<NaiveDate> = before.and_then(|before| {
-                if before.year.is_none() {
-                    let _ = cli::Cli::command()
-                        .error(
-                            ErrorKind::ValueValidation,
-                            "The year must be specified for the 'before' option.",
-                        )
-                        // TODO: remove the "usage: " section that gets displayed
-                        .print();
-                    exit(3);
-                }
-                Some(before.naive_date_safe_year())
-            });
-            // Check that the date isn't in the past
-            if let Some(before_date) = before_date {
-                if before_date < now.date_naive() {
-                    cli::Cli::command()
-                        .error(
-                            ErrorKind::ValueValidation,
-                            "The 'before' date can't be in the past.",
-                        )
-                        .exit();
+                let mut months = Table::new();
+                months.set_format(style);
+                months.set_titles(Row::new(vec![cell!("Month"), cell!("Entries")]));
+                for (index, count) in stats.month_histogram.iter().enumerate() {
+                    let month = NaiveDate::from_ymd_opt(2000, index as u32 + 1, 1).unwrap();
+                    months.add_row(Row::new(vec![
+                        cell!(month.format("%B")),
+                        cell!(format!("{} {}", count, "█".repeat(*count))),
+                    ]));
                 }
-            };
+                months.printstd();
+            }
+        }
+        cli::Commands::Gap { a, b } => {
+            let (mut older, mut older_date) = gap_birth_date(&conf_file.config.birthdays, a);
+            let (mut younger, mut younger_date) = gap_birth_date(&conf_file.config.birthdays, b);
+            if younger_date < older_date {
+                (older, younger) = (younger, older);
+                (older_date, younger_date) = (younger_date, older_date);
+            }
+
+            let plural =
+                |n: u32, unit: &str| format!("{} {}{}", n, unit, if n == 1 { "" } else { "s" });
+            match utils::years_months_days_between(older_date, younger_date) {
+                (0, 0, 0) => println!("{} and {} were born on the same day", older, younger),
+                (years, months, days) => {
+                    let parts: Vec<String> = [(years, "year"), (months, "month"), (days, "day")]
+                        .into_iter()
+                        .filter(|(n, _)| *n > 0)
+                        .map(|(n, unit)| plural(n, unit))
+                        .collect();
+                    let gap = match parts.split_last() {
+                        Some((last, [])) => last.clone(),
+                        Some((last, rest)) => format!("{} and {}", rest.join(", "), last),
+                        None => unreachable!(),
+                    };
+                    println!("{} is older than {} by {}", older, younger, gap);
+                }
+            }
+        }
+        cli::Commands::On { date } => {
+            let mut entries = parse_entries(
+                conf_file
+                    .config
+                    .birthdays
+                    .into_iter()
+                    .filter(|config_entry| !config_entry.hidden)
+                    // Entries with a date given by a weekday change of day every year, so they aren't matched
+                    .filter(|config_entry| match config_entry.date {
+                        config::EntryDate::Date(entry_date) => {
+                            entry_date.day == date.day && entry_date.month == date.month
+                        }
+                        _ => false,
+                    })
+                    .collect(),
+                None,
+                leap_day,
+            );
+            let day = date.naive_date_safe_year().format("%d %B");
+            if entries.is_empty() {
+                eprintln!("Nobody was born on {}.", day);
+                exit_if_nothing_shown(false, cli.strict);
+            }
+            entries.sort_by_cached_key(|entry| utils::collation_key(&entry.name));
+
+            println!("{}", day);
+            for entry in entries {
+                match describe_age_on_day(&entry, date.year) {
+                    Some(age) => println!("  {} ({})", entry.name, age),
+                    None => println!("  {}", entry.name),
+                }
+            }
+        }
+        cli::Commands::Show {
+            name,
+            match_exact,
+            open,
+            age_on,
+        } => {
+            let age_on: Option<NaiveDate> = age_on.map(|age_on| require_year(&age_on, "age-on"));
+            let entries = parse_entries(
+                conf_file
+                    .config
+                    .birthdays
+                    .into_iter()
+                    .filter(|config_entry| {
+                        if *match_exact {
+                            config_entry.has_name(name)
+                        } else {
+                            config_entry.has_name_containing(name)
+                        }
+                    })
+                    .collect(),
+                None,
+                leap_day,
+            );
+            if entries.is_empty() {
+                exit_with_error(
+                    ErrorCategory::Value,
+                    if *match_exact {
+                        format!("No entry named '{}' was found.", name)
+                    } else {
+                        format!("No entry matching '{}' was found.", name)
+                    },
+                );
+            }
 
-            // Parse the ConfigEntry to Entry
-            let mut entries: Vec<config::Entry> = match conf_file
+            let now: DateTime<Local> = utils::now();
+            for (index, entry) in entries.iter().enumerate() {
+                if index > 0 {
+                    println!();
+                }
+                println!("{}", entry.name);
+                if !entry.aliases.is_empty() {
+                    println!("  Also known as: {}", entry.aliases.join(", "));
+                }
+                if entry.day_unknown {
+                    let date = entry.date.naive_date_safe_year();
+                    match entry.date.year {
+                        Some(year) => {
+                            println!("  Date: {} {} (day unknown)", date.format("%B"), year)
+                        }
+                        None => println!("  Date: {} (day unknown)", date.format("%B")),
+                    }
+                } else {
+                    println!("  Date: {}", entry.date);
+                    match (entry.date.birth_weekday(), entry.kind) {
+                        (Some(weekday), config::EntryKind::Birthday) => {
+                            println!("  Born on a {}", weekday_name(weekday))
+                        }
+                        (Some(weekday), config::EntryKind::Anniversary) => {
+                            println!("  On a {}", weekday_name(weekday))
+                        }
+                        (None, _) => {}
+                    }
+                }
+                if let Some(relationship) = &entry.relationship {
+                    println!("  Relationship: {}", relationship);
+                }
+                if let Some(budget) = &entry.budget {
+                    println!("  Budget: {}", budget);
+                }
+                if let Some(photo) = &entry.photo {
+                    println!("  Photo: {}", photo.display());
+                }
+                if let Some(email) = &entry.email {
+                    println!("  Email: {}", email);
+                    println!("  Send a message: {}", mailto_link(email, None));
+                }
+                if let Some(tz) = entry.timezone {
+                    println!("  Timezone: {}", tz);
+                }
+                if let Some(time) = entry.time {
+                    println!("  Time: {}", time.format("%H:%M"));
+                }
+                if let Some(source) = &entry.source {
+                    println!("  Source: {}", source);
+                }
+                let occurence = entry.kind.occurence_name();
+                match (entry.prev_occurence, entry.next_occurence) {
+                    (_, _) if entry.day_unknown && entry.num_days_until() == 0 => {
+                        println!("  Next {}: this month", occurence)
+                    }
+                    (_, Some(next)) if entry.day_unknown => {
+                        println!(
+                            "  Next {}: in {}",
+                            occurence,
+                            next.date_naive().format("%B %Y")
+                        )
+                    }
+                    (Some(prev), Some(next)) => {
+                        println!(
+                            "  Next {}: {} ({})",
+                            occurence,
+                            humanize_until(next, now, conf_file.config.display.humanize),
+                            format_occurence(next, cli.utc)
+                        );
+                        println!(
+                            "  Previous {}: {}",
+                            occurence,
+                            format_occurence(prev, cli.utc)
+                        );
+                    }
+                    _ => println!("  Next {}: Today!", occurence),
+                }
+                match (entry.kind, entry.next_age()) {
+                    (config::EntryKind::Anniversary, Some(years)) => {
+                        println!("  Years at the next anniversary: {}", years)
+                    }
+                    (config::EntryKind::Birthday, Some(_)) => {
+                        if let Some(age) = format_age_old(entry) {
+                            println!("  Age: {}", age);
+                        }
+                    }
+                    (_, None) => {}
+                }
+                // The number of days alive isn't known without the day of birth
+                if entry.date.year.is_some() && !entry.day_unknown {
+                    let days_alive =
+                        (now.date_naive() - entry.date.naive_date_safe_year()).num_days();
+                    let milestone = utils::next_days_milestone(days_alive);
+                    match entry.kind {
+                        config::EntryKind::Birthday => println!("  Alive: {} days", days_alive),
+                        config::EntryKind::Anniversary => println!("  Since: {} days", days_alive),
+                    }
+                    println!(
+                        "  Next milestone: {} days in {} days",
+                        milestone,
+                        milestone - days_alive
+                    );
+                }
+                if let Some(age_on) = age_on {
+                    let age = match (entry.date.age_on(age_on, entry.leap_day), entry.kind) {
+                        (Some(age), config::EntryKind::Birthday) if age < 0 => {
+                            "not born yet".to_string()
+                        }
+                        (Some(years), config::EntryKind::Anniversary) if years < 0 => {
+                            "not happened yet".to_string()
+                        }
+                        (Some(age), _) => age.to_string(),
+                        (None, _) => "unknown, the year isn't known".to_string(),
+                    };
+                    let label = match entry.kind {
+                        config::EntryKind::Birthday => "Age",
+                        config::EntryKind::Anniversary => "Years",
+                    };
+                    println!(
+                        "  {} on {}: {}",
+                        label,
+                        config::BirthdayDate::from(age_on),
+                        age
+                    );
+                }
+                if let (true, Some(email)) = (open, &entry.email) {
+                    open_link(&mailto_link(email, None));
+                }
+            }
+        }
+        cli::Commands::Fmt => {
+            conf_file.normalize();
+            write_config(&conf_file);
+            println!(
+                "Formatted {} entries in {}",
+                conf_file.config.birthdays.len(),
+                conf_file.path.display()
+            );
+        }
+        cli::Commands::Export {
+            format,
+            schema,
+            separator,
+            since,
+            until,
+            all,
+        } => {
+            let date_range = DateRange::new(*since, *until);
+            let entries = parse_entries(conf_file.config.birthdays.clone(), None, leap_day);
+            let (birthdays, entries): (Vec<config::ConfigEntry>, Vec<config::Entry>) = conf_file
                 .config
                 .birthdays
                 .into_iter()
-                .map(config::Entry::try_from)
-                .collect()
-            {
-                Ok(entries) => entries,
-                Err(e) => match e {
-                    config::EntryError::TimezoneParseError(e) => {
-                        let _ = cli::Cli::command()
-                            .error(ErrorKind::Io, format!("Error parsing timezone: {}.", e))
-                            // TODO: remove the "usage: " section that gets displayed
-                            .print();
-                        exit(3);
-                    }
-                },
+                .zip(entries)
+                .filter(|(config_entry, entry)| {
+                    (*all || !config_entry.hidden) && date_range.contains(entry, utils::now())
+                })
+                .unzip();
+
+            match format {
+                cli::ExportFormat::Toml => print!(
+                    "{}",
+                    toml::to_string(&config::Config {
+                        birthdays,
+                        ..Default::default()
+                    })
+                    .expect("Error serializing entries")
+                ),
+                cli::ExportFormat::Csv => {
+                    export_csv(&entries, *schema, *separator).expect("Error writing entries")
+                }
+            }
+        }
+        cli::Commands::Import {
+            file,
+            separator,
+            check,
+            allow_future,
+            yes,
+        } => {
+            let rows = match import::read_csv(file, *separator) {
+                Ok(rows) => rows,
+                Err(e) => exit_with_error(
+                    ErrorCategory::Io,
+                    format!("Error reading {}: {}", file.display(), e),
+                ),
             };
+            let mut entries: Vec<config::ConfigEntry> = vec![];
+            let mut invalid = 0;
+            let mut duplicates = 0;
+            let today = utils::now().date_naive();
+            for (line, row) in rows {
+                match row {
+                    // Same check as the add command, as such years are likely typos
+                    Ok(entry) if !allow_future && entry.date.has_future_year(today) => {
+                        eprintln!(
+                            "Line {}: the year of birth is in the future, use --allow-future if this is intended",
+                            line
+                        );
+                        invalid += 1;
+                    }
+                    // Entries that are already in the birthday file (or earlier in the CSV file) are skipped
+                    Ok(entry)
+                        if conf_file
+                            .config
+                            .birthdays
+                            .iter()
+                            .chain(entries.iter())
+                            .any(|other| entry.is_duplicate_of(other)) =>
+                    {
+                        duplicates += 1
+                    }
+                    Ok(entry) => entries.push(entry),
+                    Err(e) => {
+                        eprintln!("Line {}: {}", line, e);
+                        invalid += 1;
+                    }
+                }
+            }
 
-            // Sort the entries by date of next occurence
-            // TODO: Maybe move this earlier to we don't have to use mut on entries
-            entries.sort_by(|a, b| b.next_occurence.cmp(&a.next_occurence));
-
-            let mut table = Table::new();
-            // table.set_format(*format::consts::FORMAT_BOX_CHARS);
-            table.set_format(
-                format::FormatBuilder::new()
-                    .column_separator('│')
-                    .borders('│')
-                    .separators(
-                        &[format::LinePosition::Top],
-                        format::LineSeparator::new('─', '┬', '╭', '╮'),
-                    )
-                    .separators(
-                        &[format::LinePosition::Intern],
-                        format::LineSeparator::new('─', '┼', '├', '┤'),
-                    )
-                    .separators(
-                        &[format::LinePosition::Bottom],
-                        format::LineSeparator::new('─', '┴', '╰', '╯'),
-                    )
-                    .padding(1, 1)
-                    .build(),
-            );
+            if *check {
+                println!(
+                    "{} valid rows ({} duplicates), {} invalid rows",
+                    entries.len() + duplicates,
+                    duplicates,
+                    invalid
+                );
+            } else if invalid > 0 {
+                exit_with_error(
+                    ErrorCategory::Value,
+                    format!("{} rows are invalid, nothing was imported.", invalid),
+                );
+            } else {
+                println!(
+                    "About to import {} entries, {} duplicates will be skipped",
+                    entries.len(),
+                    duplicates
+                );
+                if entries.len() > conf_file.config.import.confirm_above && !yes {
+                    if !io::stdin().is_terminal() {
+                        exit_with_error(
+                            ErrorCategory::Io,
+                            format!(
+                                "Confirmation is needed to import more than {} entries, use --yes to give it without a terminal.",
+                                conf_file.config.import.confirm_above
+                            ),
+                        );
+                    }
+                    let confirmed = prompt("Import them? [y/N]", |answer| {
+                        match answer.to_lowercase().as_str() {
+                            "y" | "yes" => Ok(true),
+                            "" | "n" | "no" => Ok(false),
+                            _ => Err("Answer y or n.".to_string()),
+                        }
+                    });
+                    if !confirmed {
+                        println!("Nothing was imported");
+                        return;
+                    }
+                }
+                let count = entries.len();
+                let source = format!(
+                    "imported from {} on {}",
+                    file.display(),
+                    utils::now().format("%Y-%m-%d")
+                );
+                for mut entry in entries {
+                    entry.source = Some(source.clone());
+                    conf_file.add_entry(entry);
+                }
+                write_config(&conf_file);
+                println!("Imported {} entries from {}", count, file.display());
+            }
+        }
+        // Handled before loading the birthday file
+        cli::Commands::Where => unreachable!(),
+        cli::Commands::Rename { from, to, index } => {
+            let matches: Vec<usize> = conf_file
+                .config
+                .birthdays
+                .iter()
+                .enumerate()
+                .filter(|(_, entry)| &entry.name == from)
+                .map(|(i, _)| i)
+                .collect();
 
-            // Makes the header bold
-            table.set_titles(row![b => "#", "Name", "Date", "Age", "In"]);
+            let position = match (matches.len(), index) {
+                (0, _) => exit_with_error(
+                    ErrorCategory::Value,
+                    format!("No entry named '{}' was found.", from),
+                ),
+                (1, None) => matches[0],
+                (count, None) => exit_with_error(
+                    ErrorCategory::Value,
+                    format!(
+                        "{} entries are named '{}', use --index to pick which one to rename.",
+                        count, from
+                    ),
+                ),
+                (count, Some(i)) => match i.checked_sub(1).and_then(|i| matches.get(i)) {
+                    Some(position) => *position,
+                    None => exit_with_error(
+                        ErrorCategory::Value,
+                        format!(
+                            "Invalid index {}, there are {} entries named '{}'.",
+                            i, count, from
+                        ),
+                    ),
+                },
+            };
 
-            let mut iter = entries
+            conf_file.rename_entry(position, to);
+            write_config(&conf_file);
+            println!("Renamed entry {} to {}", from, to);
+        }
+        cli::Commands::Prune {
+            hidden,
+            before_year,
+            yes,
+        } => {
+            let positions: Vec<usize> = conf_file
+                .config
+                .birthdays
                 .iter()
-                .rev()
-                .take(limit.unwrap_or(entries.len()))
-                .rev()
-                // Only show entries that will happen before or durign before_date
-                .filter(|entry: &&config::Entry| {
-                    before_date
-                        .map(|before_date| {
-                            entry.next_occurence.unwrap_or(Local::now()).date_naive() <= before_date
+                .enumerate()
+                .filter(|(_, entry)| {
+                    (*hidden && entry.hidden)
+                        || before_year.is_some_and(|before| {
+                            entry.date.year().is_some_and(|year| year < before)
                         })
-                        .unwrap_or(true)
                 })
-                .peekable();
+                .map(|(position, _)| position)
+                .collect();
+            if positions.is_empty() {
+                println!("No entries match, nothing was removed");
+                return;
+            }
 
-            if iter.peek().is_none() {
-                eprintln!("No entries match the given criteria.");
-                exit(0);
+            let names: Vec<&str> = positions
+                .iter()
+                .map(|position| conf_file.config.birthdays[*position].name.as_str())
+                .collect();
+            println!("Entries to remove: {}", names.join(", "));
+            if !yes {
+                if !io::stdin().is_terminal() {
+                    exit_with_error(
+                        ErrorCategory::Io,
+                        "Confirmation is needed to remove entries, use --yes to give it without a terminal.",
+                    );
+                }
+                let confirmed = prompt(
+                    &format!("Remove {} entries? [y/N]", positions.len()),
+                    |answer| match answer.to_lowercase().as_str() {
+                        "y" | "yes" => Ok(true),
+                        "" | "n" | "no" => Ok(false),
+                        _ => Err("Answer y or n.".to_string()),
+                    },
+                );
+                if !confirmed {
+                    println!("Nothing was removed");
+                    return;
+                }
             }
 
-            for (index, entry) in iter.enumerate() {
-                let new_age: Option<i32> = entry
-                    .date
-                    .year
-                    // If next_occurence is None, it means the birthday is today, so we use now
-                    .map(|y| entry.next_occurence.unwrap_or(Local::now()).year() - y);
+            conf_file.remove_entries(&positions);
+            write_config(&conf_file);
+            println!("Removed {} entries", positions.len());
+        }
+        cli::Commands::Undo => {
+            let backup_path = config::backup_path(&conf_file.path);
+            if !backup_path.exists() {
+                exit_with_error(
+                    ErrorCategory::Io,
+                    format!(
+                        "There is nothing to undo, no backup of the birthday file was found at {}.",
+                        backup_path.display()
+                    ),
+                );
+            }
+            let backup = load_config_file(backup_path.clone());
 
-                table.add_row(row![
-                    index + 1,
-                    entry.name,
-                    // Chrono doesn't support locales yet
-                    // entry.date.format("%C").to_string(),
-                    entry.date.naive_date_safe_year().format("%d %B"),
-                    match new_age {
-                        Some(age) => format!("{} 🡒 {}", age - 1, age),
-                        None => "?".to_string(),
-                    },
-                    match entry.next_occurence {
-                        Some(dt) => HumanTime::from(dt - now).to_string(),
-                        None => "Today!".to_string(),
-                    }
-                ]);
+            // The current content becomes the backup, so undoing again redoes the change.
+            // Both files are copied as they are, not as they were parsed
+            let swap = || -> io::Result<()> {
+                let current = match fs::read(&conf_file.path) {
+                    Ok(current) => Some(current),
+                    Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+                    Err(e) => return Err(e),
+                };
+                replace_file(&conf_file.path, &fs::read(&backup_path)?)?;
+                match current {
+                    Some(current) => replace_file(&backup_path, &current),
+                    None => fs::remove_file(&backup_path),
+                }
+            };
+            if let Err(e) = swap() {
+                exit_with_error(
+                    ErrorCategory::Io,
+                    format!("Error restoring the backup of the birthday file: {}", e),
+                );
             }
 
-            table.printstd();
+            let names = |config: &config::Config| -> Vec<String> {
+                config
+                    .birthdays
+                    .iter()
+                    .map(|entry| entry.name.clone())
+                    .collect()
+            };
+            let (current_names, backup_names) = (names(&conf_file.config), names(&backup.config));
+            let current_names: Vec<&str> = current_names.iter().map(String::as_str).collect();
+            let backup_names: Vec<&str> = backup_names.iter().map(String::as_str).collect();
+            println!("Restored {} from its backup", conf_file.path.display());
+            let removed = missing_names(&current_names, &backup_names);
+            if !removed.is_empty() {
+                println!("  Removed: {}", removed.join(", "));
+            }
+            let restored = missing_names(&backup_names, &current_names);
+            if !restored.is_empty() {
+                println!("  Restored: {}", restored.join(", "));
+            }
+            if removed.is_empty() && restored.is_empty() {
+                println!("  The same entries were kept, with their previous content");
+            }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        agenda_dates, cli, describe_age_on_day, format_json_line, format_json_list, select_entries,
+        table_format, utils, DateRange,
+    };
+    use crate::config::{sort_entries, test_entry, Entry, TableStyle};
+    use chrono::{Local, NaiveDate, TimeZone};
+    use clap::Parser;
+    use prettytable::{Row, Table};
+    use test_case::test_case;
+
+    #[test_case(TableStyle::Rounded ; "Rounded")]
+    #[test_case(TableStyle::Box ; "Box drawing")]
+    #[test_case(TableStyle::Ascii ; "Ascii")]
+    fn test_table_borders_aligned_with_wide_names(style: TableStyle) {
+        let mut table = Table::new();
+        table.set_format(table_format(style));
+        table.set_titles(Row::from(["#", "Name", "Date"]));
+        for (index, name) in ["山田太郎", "🎂 Mom", "Bob"].into_iter().enumerate() {
+            table.add_row(Row::from([
+                (index + 1).to_string().as_str(),
+                name,
+                "01 January",
+            ]));
+        }
+        let rendered = table.to_string();
+        let widths: Vec<usize> = rendered.lines().map(utils::display_width).collect();
+        assert!(
+            widths.iter().all(|width| *width == widths[0]),
+            "{}",
+            rendered
+        );
+    }
+
+    #[test_case("name = 'Alice \"Al\"'\nday = 24\nmonth = 12\nyear = 1990",
+                r#"{"name": "Alice \"Al\"", "date": "1990-12-24", "kind": "birthday", "next": "2024-12-24", "days": 3, "age": 34}"# ;
+                "Known year")]
+    #[test_case("name = 'Wedding'\nday = 1\nmonth = 1\nyear = 2000\nkind = 'anniversary'",
+                r#"{"name": "Wedding", "date": "2000-01-01", "kind": "anniversary", "next": "2025-01-01", "days": 11, "age": 25}"# ;
+                "Anniversary")]
+    #[test_case("name = 'Bob'\nmonth = 3",
+                r#"{"name": "Bob", "date": "--03", "kind": "birthday", "next": null, "days": 70, "age": null}"# ;
+                "Without a day nor a year")]
+    fn test_format_json_line(entry: &str, expected: &str) {
+        let entry = test_entry(entry, NaiveDate::from_ymd_opt(2024, 12, 21));
+        assert_eq!(format_json_line(&entry), expected);
+    }
+
+    #[test]
+    fn test_format_json_list() {
+        let today = NaiveDate::from_ymd_opt(2024, 12, 21);
+        let entries: Vec<Entry> = [
+            "name = 'Alice'\nday = 24\nmonth = 12",
+            "name = 'Bob'\nmonth = 3",
+        ]
+        .into_iter()
+        .map(|entry| test_entry(entry, today))
+        .collect();
+        let shown: Vec<&Entry> = entries.iter().take(1).collect();
+        assert_eq!(
+            format_json_list(&shown, entries.len()),
+            format!(
+                r#"{{"total": 2, "shown": 1, "entries": [{}]}}"#,
+                format_json_line(&entries[0])
+            )
+        );
+        assert_eq!(
+            format_json_list(&[], 0),
+            r#"{"total": 0, "shown": 0, "entries": []}"#
+        );
+    }
+
+    #[test_case("kind = 'birthday'", Some(2030), Some("turning 40") ; "Given year")]
+    #[test_case("kind = 'birthday'", None, Some("turning 35") ; "Next birthday")]
+    #[test_case("kind = 'birthday'", Some(1990), Some("born that day") ; "Year of birth")]
+    #[test_case("kind = 'birthday'", Some(1980), Some("not born yet") ; "Year before the birth")]
+    #[test_case("kind = 'anniversary'", Some(2030), Some("40 years") ; "Anniversary")]
+    #[test_case("kind = 'anniversary'", Some(1980), Some("not happened yet") ; "Year before the anniversary")]
+    fn test_describe_age_on_day(kind: &str, year: Option<i32>, expected: Option<&str>) {
+        let entry = test_entry(
+            &format!("name = 'A'\nday = 24\nmonth = 12\nyear = 1990\n{}", kind),
+            NaiveDate::from_ymd_opt(2025, 12, 21),
+        );
+        assert_eq!(describe_age_on_day(&entry, year).as_deref(), expected);
+    }
+
+    #[test]
+    fn test_describe_age_on_day_without_year_of_birth() {
+        let entry = test_entry("name = 'A'\nday = 24\nmonth = 12", None);
+        assert_eq!(describe_age_on_day(&entry, Some(2030)), None);
+    }
+
+    /// The options of a list command line, such as ["--limit", "2"]
+    fn list_args(args: &[&str]) -> cli::ListArgs {
+        let args = ["bday", "list"].iter().chain(args);
+        match cli::Cli::parse_from(args).command {
+            cli::Commands::List(list_args) => list_args,
+            _ => unreachable!(),
+        }
+    }
+
+    /// The names of the entries selected by list on 21 december 2024, with entries on the given days and months
+    fn selected_names(args: &[&str], dates: &[(u32, u32)]) -> Vec<String> {
+        let today = NaiveDate::from_ymd_opt(2024, 12, 21);
+        let mut entries: Vec<Entry> = dates
+            .iter()
+            .map(|(day, month)| {
+                let name = format!("{:02}/{:02}", day, month);
+                test_entry(
+                    &format!("name = '{}'\nday = {}\nmonth = {}", name, day, month),
+                    today,
+                )
+            })
+            .collect();
+        sort_entries(&mut entries);
+        let args = list_args(args);
+        let now = Local.with_ymd_and_hms(2024, 12, 21, 12, 0, 0).unwrap();
+        let date_range = DateRange::new(args.since, args.until);
+        let before_date = args.before.map(|before| before.naive_date_safe_year());
+        select_entries(&entries, &args, now, before_date, &date_range)
+            .iter()
+            .map(|entry| entry.name.clone())
+            .collect()
+    }
+
+    #[test]
+    fn test_select_entries_limit_after_date_range() {
+        let dates = [(22, 12), (25, 12), (5, 1)];
+        assert_eq!(
+            selected_names(&["--limit", "1", "--since", "2024-12-24"], &dates),
+            vec!["25/12"]
+        );
+    }
+
+    #[test]
+    fn test_agenda_dates_february_29th_on_non_leap_year() {
+        let today = NaiveDate::from_ymd_opt(2024, 12, 21);
+        let mut entries: Vec<Entry> = [
+            "name = 'Leap'\nday = 29\nmonth = 2",
+            "name = 'Eve'\nday = 28\nmonth = 2",
+            "name = 'March'\nday = 1\nmonth = 3",
+        ]
+        .into_iter()
+        .map(|entry| test_entry(entry, today))
+        .collect();
+        sort_entries(&mut entries);
+        let dates: Vec<(NaiveDate, Vec<&str>)> = agenda_dates(&entries)
+            .into_iter()
+            .map(|(date, entries)| {
+                (
+                    date,
+                    entries.iter().map(|entry| entry.name.as_str()).collect(),
+                )
+            })
+            .collect();
+        // February 29th is celebrated on the 28th in 2025
+        let mut february = dates[0].1.clone();
+        february.sort();
+        assert_eq!(dates[0].0, NaiveDate::from_ymd_opt(2025, 2, 28).unwrap());
+        assert_eq!(february, vec!["Eve", "Leap"]);
+        assert_eq!(
+            dates[1],
+            (NaiveDate::from_ymd_opt(2025, 3, 1).unwrap(), vec!["March"])
+        );
+    }
+
+    #[test]
+    fn test_select_entries_before_excludes_until_includes() {
+        let dates = [(22, 12), (25, 12), (5, 1)];
+        assert_eq!(
+            selected_names(&["--before", "2024-12-25"], &dates),
+            vec!["22/12"]
+        );
+        assert_eq!(
+            selected_names(&["--until", "2024-12-25"], &dates),
+            vec!["25/12", "22/12"]
+        );
+    }
+
+    #[test]
+    fn test_select_entries_limit_after_within() {
+        // The entries of 22/12 and 25/12 are within 5 days, the closest one is kept
+        let dates = [(22, 12), (25, 12), (5, 1)];
+        assert_eq!(
+            selected_names(&["--limit", "1", "--within", "5"], &dates),
+            vec!["22/12"]
+        );
+        assert_eq!(
+            selected_names(&["--limit", "5", "--within", "5"], &dates),
+            vec!["25/12", "22/12"]
+        );
+    }
+
+    #[test]
+    fn test_select_entries_limit_after_timezone() {
+        let today = NaiveDate::from_ymd_opt(2024, 12, 21);
+        let mut entries: Vec<Entry> = [
+            "name = 'Local'\nday = 22\nmonth = 12",
+            "name = 'Tokyo'\nday = 25\nmonth = 12\ntimezone = 'Asia/Tokyo'",
+        ]
+        .into_iter()
+        .map(|entry| test_entry(entry, today))
+        .collect();
+        sort_entries(&mut entries);
+        let args = list_args(&["--limit", "1", "--timezone", "Asia/Tokyo"]);
+        let now = Local.with_ymd_and_hms(2024, 12, 21, 12, 0, 0).unwrap();
+        let shown = select_entries(&entries, &args, now, None, &DateRange::new(None, None));
+        let names: Vec<&str> = shown.iter().map(|entry| entry.name.as_str()).collect();
+        assert_eq!(names, vec!["Tokyo"]);
+    }
+
+    #[test]
+    fn test_select_entries_limit_after_month() {
+        let dates = [(22, 12), (25, 12), (5, 1), (3, 3), (10, 3)];
+        assert_eq!(
+            selected_names(&["--limit", "2", "--month", "3"], &dates),
+            vec!["10/03", "03/03"]
+        );
+        assert_eq!(
+            selected_names(&["--limit", "1", "--month", "12"], &dates),
+            vec!["22/12"]
+        );
+    }
+}
@@ -0,0 +1,169 @@
+//! Statistics about the entries of the birthday file, printed by the stats command.
+
+use crate::config::{Entry, EntryKind};
+use crate::utils;
+use chrono::Month;
+
+/// Statistics about a set of entries
+pub struct Stats<'a> {
+    /// The number of entries
+    pub total: usize,
+    /// The number of entries with a known year
+    pub with_year: usize,
+    /// The average current age of the people with a known year of birth, rounded to one decimal.
+    /// Anniversaries are left out, as they aren't the age of someone
+    pub average_age: Option<f64>,
+    /// The oldest person with a known year of birth, anniversaries are left out too
+    pub oldest: Option<&'a Entry>,
+    /// The number of entries in each month, from january to december
+    pub month_histogram: [usize; 12],
+}
+
+impl<'a> Stats<'a> {
+    pub fn new(entries: &'a [Entry]) -> Self {
+        let people: Vec<&Entry> = entries
+            .iter()
+            .filter(|entry| entry.kind == EntryKind::Birthday && entry.date.year.is_some())
+            .collect();
+        let ages: Vec<i32> = people.iter().filter_map(|entry| entry.age_now()).collect();
+        let average_age = match ages.len() {
+            0 => None,
+            count => {
+                let average = ages.iter().sum::<i32>() as f64 / count as f64;
+                Some((average * 10.0).round() / 10.0)
+            }
+        };
+        let oldest = people.into_iter().min_by_key(|entry| {
+            (
+                entry.date.naive_date_safe_year(),
+                utils::collation_key(&entry.name),
+            )
+        });
+
+        let mut month_histogram = [0; 12];
+        for entry in entries {
+            month_histogram[entry.date.month as usize - 1] += 1;
+        }
+
+        Self {
+            total: entries.len(),
+            with_year: entries
+                .iter()
+                .filter(|entry| entry.date.year.is_some())
+                .count(),
+            average_age,
+            oldest,
+            month_histogram,
+        }
+    }
+
+    /// The statistics as a JSON object on a single line, such as
+    /// {"total": 3, "with_year": 1, "average_age": 34, "oldest": {"name": "Alice", "year": 1990, "age": 34},
+    /// "month_histogram": {"january": 0, ...}}.
+    /// The average age and the oldest person are null if no year of birth is known.
+    pub fn to_json(&self) -> String {
+        let average_age = match self.average_age {
+            Some(average_age) => average_age.to_string(),
+            None => "null".to_string(),
+        };
+        let oldest = match self.oldest {
+            Some(entry) => format!(
+                "{{\"name\": {}, \"year\": {}, \"age\": {}}}",
+                utils::json_string(&entry.name),
+                entry.date.year.unwrap(),
+                entry.age_now().unwrap()
+            ),
+            None => "null".to_string(),
+        };
+        let month_histogram: Vec<String> = self
+            .month_histogram
+            .iter()
+            .enumerate()
+            .map(|(index, count)| {
+                let month = Month::try_from(index as u8 + 1).unwrap();
+                format!(
+                    "{}: {}",
+                    utils::json_string(&month.name().to_lowercase()),
+                    count
+                )
+            })
+            .collect();
+        format!(
+            "{{\"total\": {}, \"with_year\": {}, \"average_age\": {}, \"oldest\": {}, \"month_histogram\": {{{}}}}}",
+            self.total,
+            self.with_year,
+            average_age,
+            oldest,
+            month_histogram.join(", ")
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Stats;
+    use crate::config::{test_entry, Entry};
+    use chrono::NaiveDate;
+
+    fn entries(entries: &[&str]) -> Vec<Entry> {
+        let today = NaiveDate::from_ymd_opt(2024, 12, 21);
+        entries
+            .iter()
+            .map(|entry| test_entry(entry, today))
+            .collect()
+    }
+
+    #[test]
+    fn test_stats() {
+        let entries = entries(&[
+            "name = 'Alice'\nday = 24\nmonth = 12\nyear = 1990",
+            "name = 'Bob'\nday = 1\nmonth = 1\nyear = 1995",
+            "name = 'Wedding'\nday = 2\nmonth = 1\nyear = 1980\nkind = 'anniversary'",
+            "name = 'Carol'\nmonth = 3",
+        ]);
+        let stats = Stats::new(&entries);
+        assert_eq!(stats.total, 4);
+        assert_eq!(stats.with_year, 3);
+        // Alice is 33 and Bob is 29, the anniversary is left out
+        assert_eq!(stats.average_age, Some(31.0));
+        assert_eq!(stats.oldest.map(|entry| entry.name.as_str()), Some("Alice"));
+        assert_eq!(stats.month_histogram, [2, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 1]);
+    }
+
+    #[test]
+    fn test_stats_rounds_average_age() {
+        let entries = entries(&[
+            "name = 'Alice'\nday = 1\nmonth = 1\nyear = 1990",
+            "name = 'Bob'\nday = 1\nmonth = 1\nyear = 1991",
+            "name = 'Carol'\nday = 1\nmonth = 1\nyear = 1991",
+        ]);
+        // The ages are 34, 33 and 33
+        assert_eq!(Stats::new(&entries).average_age, Some(33.3));
+    }
+
+    #[test]
+    fn test_stats_to_json() {
+        let entries = entries(&[
+            "name = 'Alice \"Al\"'\nday = 24\nmonth = 12\nyear = 1990",
+            "name = 'Bob'\nday = 1\nmonth = 2",
+        ]);
+        assert_eq!(
+            Stats::new(&entries).to_json(),
+            concat!(
+                r#"{"total": 2, "with_year": 1, "average_age": 33, "#,
+                r#""oldest": {"name": "Alice \"Al\"", "year": 1990, "age": 33}, "#,
+                r#""month_histogram": {"january": 0, "february": 1, "march": 0, "april": 0, "may": 0, "#,
+                r#""june": 0, "july": 0, "august": 0, "september": 0, "october": 0, "november": 0, "#,
+                r#""december": 1}}"#
+            )
+        );
+    }
+
+    #[test]
+    fn test_stats_to_json_without_years() {
+        let entries = entries(&["name = 'Bob'\nday = 1\nmonth = 2"]);
+        let json = Stats::new(&entries).to_json();
+        assert!(json
+            .starts_with(r#"{"total": 1, "with_year": 0, "average_age": null, "oldest": null, "#));
+    }
+}
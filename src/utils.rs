@@ -1,14 +1,37 @@
-use chrono::{Datelike, NaiveDate};
+use crate::config::{Hemisphere, LeapDay};
+use chrono::{
+    DateTime, Datelike, Local, LocalResult, Months, NaiveDate, NaiveDateTime, TimeDelta, TimeZone,
+    Utc, Weekday,
+};
+use std::cell::Cell;
+use unicode_width::UnicodeWidthChar;
+
+thread_local! {
+    /// The instant used as the current time instead of the system clock, set by --now.
+    /// It is per thread so tests, which each run on their own thread, can use different instants
+    static NOW: Cell<Option<DateTime<Utc>>> = const { Cell::new(None) };
+}
+
+/// Use the given instant as the current time for the rest of the run
+pub fn set_now(now: DateTime<Utc>) {
+    NOW.set(Some(now));
+}
+
+/// The current time, which is the one given to set_now if it was called
+pub fn now() -> DateTime<Local> {
+    NOW.get().unwrap_or_else(Utc::now).with_timezone(&Local)
+}
 
 /// Add a number of years to a date.
-/// Handles the february 29th case, by returning february 28th on non-leap years.
-fn make_date_safe(year: i32, month: u32, day: u32) -> NaiveDate {
+/// Handles the february 29th case, by returning february 28th or march 1st on non-leap years.
+pub fn make_date_safe(year: i32, month: u32, day: u32, leap_day: LeapDay) -> NaiveDate {
     // The match None branch is mainly to handle the february 29th case
     // I can't think of any other case where with_year would return None, so i'm not handling it
-    match NaiveDate::from_ymd_opt(year, month, day) {
-        Some(date) => date,
+    match (NaiveDate::from_ymd_opt(year, month, day), leap_day) {
+        (Some(date), _) => date,
         // Try the previous day (so feb 29th becomes feb 28th)
-        None => NaiveDate::from_ymd_opt(year, month, day - 1).unwrap(),
+        (None, LeapDay::Feb28) => NaiveDate::from_ymd_opt(year, month, day - 1).unwrap(),
+        (None, LeapDay::Mar01) => NaiveDate::from_ymd_opt(year, month + 1, 1).unwrap(),
     }
 }
 
@@ -18,45 +41,385 @@ pub fn find_prev_next_occurences(
     day: u32,
     month: u32,
     date: NaiveDate,
+    leap_day: LeapDay,
 ) -> Option<(NaiveDate, NaiveDate)> {
     let current_year = date.year();
 
-    let curr_year_birthday = make_date_safe(current_year, month, day);
+    let curr_year_birthday = make_date_safe(current_year, month, day, leap_day);
 
     // If the birthday is today, return None
-    if date.day() == day && date.month() == month {
+    if curr_year_birthday == date {
         return None;
     }
 
     // If the birthday already happened this year
     if curr_year_birthday < date {
-        let next_year_birthday = make_date_safe(current_year + 1, month, day);
+        let next_year_birthday = make_date_safe(current_year + 1, month, day, leap_day);
         Some((curr_year_birthday, next_year_birthday))
     // If the birthday hasn't happened yet this year
     } else {
-        let prev_year_birthday = make_date_safe(current_year - 1, month, day);
+        let prev_year_birthday = make_date_safe(current_year - 1, month, day, leap_day);
         Some((prev_year_birthday, curr_year_birthday))
     }
 }
 
+/// Get the n-th given weekday of a month.
+/// If the month has less than n such weekdays, the last one is returned instead.
+fn nth_weekday_of_month(year: i32, month: u32, weekday: Weekday, n: u8) -> NaiveDate {
+    // Every month has at least 4 of each weekday, so this only loops when n is 5
+    (1..=n)
+        .rev()
+        .find_map(|n| NaiveDate::from_weekday_of_month_opt(year, month, weekday, n))
+        .unwrap()
+}
+
+/// Get the previous and next occurences of a recurring "n-th weekday of a month" date
+/// (e.g. the second sunday of may), relative to a given date.  
+/// If the date is the same as the "date" argument, the result is None.  
+pub fn find_prev_next_weekday_occurences(
+    week_of_month: u8,
+    weekday: Weekday,
+    month: u32,
+    date: NaiveDate,
+) -> Option<(NaiveDate, NaiveDate)> {
+    let current_year = date.year();
+
+    let curr_year_occurence = nth_weekday_of_month(current_year, month, weekday, week_of_month);
+
+    // If the occurence is today, return None
+    if curr_year_occurence == date {
+        return None;
+    }
+
+    // If the occurence already happened this year
+    if curr_year_occurence < date {
+        let next_year_occurence =
+            nth_weekday_of_month(current_year + 1, month, weekday, week_of_month);
+        Some((curr_year_occurence, next_year_occurence))
+    // If the occurence hasn't happened yet this year
+    } else {
+        let prev_year_occurence =
+            nth_weekday_of_month(current_year - 1, month, weekday, week_of_month);
+        Some((prev_year_occurence, curr_year_occurence))
+    }
+}
+
+/// Get the first day of the previous and next occurences of a month, relative to a given date.
+/// During the month itself, the next occurence is the first day of the current month,
+/// as the day could still be ahead.
+pub fn find_prev_next_month_occurences(month: u32, date: NaiveDate) -> (NaiveDate, NaiveDate) {
+    let next_year = if month < date.month() {
+        date.year() + 1
+    } else {
+        date.year()
+    };
+    (
+        NaiveDate::from_ymd_opt(next_year - 1, month, 1).unwrap(),
+        NaiveDate::from_ymd_opt(next_year, month, 1).unwrap(),
+    )
+}
+
+/// How many days are between two milestones, so 1000 days from birth, 2000 days...
+pub const DAYS_MILESTONE_INTERVAL: i64 = 1000;
+
+/// Get the next round number of days alive (a multiple of `DAYS_MILESTONE_INTERVAL`),
+/// strictly after the given number of days alive.
+pub fn next_days_milestone(days_alive: i64) -> i64 {
+    (days_alive / DAYS_MILESTONE_INTERVAL + 1) * DAYS_MILESTONE_INTERVAL
+}
+
+/// The first number of days alive that is celebrated
+pub const FIRST_CELEBRATED_MILESTONE: i64 = 10000;
+
+/// How many days are between two celebrated milestones, so 10000 days, 15000 days...
+pub const CELEBRATED_MILESTONE_INTERVAL: i64 = 5000;
+
+/// Get the next number of days alive that is celebrated (10000, 15000, 20000...),
+/// which can be the given number of days alive itself if it is one.
+pub fn next_celebrated_milestone(days_alive: i64) -> i64 {
+    if days_alive <= FIRST_CELEBRATED_MILESTONE {
+        return FIRST_CELEBRATED_MILESTONE;
+    }
+    (days_alive + CELEBRATED_MILESTONE_INTERVAL - 1) / CELEBRATED_MILESTONE_INTERVAL
+        * CELEBRATED_MILESTONE_INTERVAL
+}
+
+/// Get the traditional birthstone of a month.
+pub fn birthstone(month: u32) -> &'static str {
+    match month {
+        1 => "Garnet",
+        2 => "Amethyst",
+        3 => "Aquamarine",
+        4 => "Diamond",
+        5 => "Emerald",
+        6 => "Pearl",
+        7 => "Ruby",
+        8 => "Peridot",
+        9 => "Sapphire",
+        10 => "Opal",
+        11 => "Topaz",
+        12 => "Turquoise",
+        _ => panic!("Invalid month: {}", month),
+    }
+}
+
+/// Get the English ordinal suffix of a day of the month, such as "st" for 1 or "th" for 11.
+pub fn ordinal_suffix(day: u32) -> &'static str {
+    match (day % 10, day % 100) {
+        (_, 11..=13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    }
+}
+
+/// Get a seasonal emoji for a month.
+pub fn month_emoji(month: u32) -> char {
+    match month {
+        1 => '⛄',
+        2 => '💘',
+        3 => '🍀',
+        4 => '🌸',
+        5 => '🌷',
+        6 => '🌞',
+        7 => '🍦',
+        8 => '🍉',
+        9 => '🍎',
+        10 => '🎃',
+        11 => '🍂',
+        12 => '❄',
+        _ => panic!("Invalid month: {}", month),
+    }
+}
+
+/// A season of the year, using the meteorological seasons of 3 whole months each
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Season {
+    Winter,
+    Spring,
+    Summer,
+    Autumn,
+}
+
+impl Season {
+    /// The english name of the season
+    pub fn name(&self) -> &'static str {
+        match self {
+            Season::Winter => "Winter",
+            Season::Spring => "Spring",
+            Season::Summer => "Summer",
+            Season::Autumn => "Autumn",
+        }
+    }
+}
+
+/// Get the season a month is in, in the given hemisphere.
+pub fn season_of(month: u32, hemisphere: Hemisphere) -> Season {
+    let northern = match month {
+        12 | 1 | 2 => Season::Winter,
+        3..=5 => Season::Spring,
+        6..=8 => Season::Summer,
+        9..=11 => Season::Autumn,
+        _ => panic!("Invalid month: {}", month),
+    };
+    match hemisphere {
+        Hemisphere::Northern => northern,
+        Hemisphere::Southern => match northern {
+            Season::Winter => Season::Summer,
+            Season::Spring => Season::Autumn,
+            Season::Summer => Season::Winter,
+            Season::Autumn => Season::Spring,
+        },
+    }
+}
+
+/// Get the time in the timezone corresponding to the given local time.
+/// If the local time happens twice (when the clock goes back for DST), the earliest one is used,
+/// and if it doesn't exist (when the clock goes forward), the first valid time after it is used.
+pub fn localize<Tz: TimeZone>(timezone: &Tz, dt: NaiveDateTime) -> DateTime<Tz> {
+    // Timezones don't skip more than a day, so a valid minute is always found
+    (0..=24 * 60)
+        .find_map(|minutes| {
+            match timezone.from_local_datetime(&(dt + TimeDelta::minutes(minutes))) {
+                LocalResult::Single(dt) => Some(dt),
+                LocalResult::Ambiguous(earliest, _) => Some(earliest),
+                LocalResult::None => None,
+            }
+        })
+        .expect("No valid time found in the day")
+}
+
+/// Get the number of calendar days between two times, in the timezone of the times.  
+/// Unlike the duration between them, this isn't affected by DST transitions.
+pub fn days_between<Tz: TimeZone>(from: &DateTime<Tz>, to: &DateTime<Tz>) -> i64 {
+    (to.date_naive() - from.date_naive()).num_days()
+}
+
+/// Get the number of full years, then full months, then days from a date to a later one.
+/// Months are added to the earlier date first, clamped to the end of the month.
+pub fn years_months_days_between(from: NaiveDate, to: NaiveDate) -> (u32, u32, u32) {
+    let mut months = (to.year() - from.year()) * 12 + to.month() as i32 - from.month() as i32;
+    if to.day() < from.day() {
+        months -= 1;
+    }
+    let months = months.max(0) as u32;
+    let days = (to - (from + Months::new(months))).num_days() as u32;
+    (months / 12, months % 12, days)
+}
+
+/// Describe a number of calendar days from today as "Today" or "Tomorrow", if possible.
+pub fn relative_day(days: i64) -> Option<&'static str> {
+    match days {
+        0 => Some("Today"),
+        1 => Some("Tomorrow"),
+        _ => None,
+    }
+}
+
+/// The base letters of the latin letters with diacritics, for each group of such letters
+const LETTERS_WITH_DIACRITICS: [(&str, &str); 24] = [
+    ("àáâãäåāăą", "a"),
+    ("æ", "ae"),
+    ("çćĉċč", "c"),
+    ("ďđð", "d"),
+    ("èéêëēĕėęě", "e"),
+    ("ĝğġģ", "g"),
+    ("ĥħ", "h"),
+    ("ìíîïĩīĭįı", "i"),
+    ("ĵ", "j"),
+    ("ķ", "k"),
+    ("ĺļľŀł", "l"),
+    ("ñńņňŉ", "n"),
+    ("òóôõöøōŏő", "o"),
+    ("œ", "oe"),
+    ("ŕŗř", "r"),
+    ("śŝşšș", "s"),
+    ("ß", "ss"),
+    ("ţťŧț", "t"),
+    ("þ", "th"),
+    ("ùúûüũūŭůűų", "u"),
+    ("ŵ", "w"),
+    ("ýÿŷ", "y"),
+    ("źżž", "z"),
+    ("ĳ", "ij"),
+];
+
+/// Get the key a name is sorted and matched by, which ignores case and the diacritics of latin letters,
+/// so "María" is sorted next to "Maria" instead of after "Mariz".
+pub fn collation_key(name: &str) -> String {
+    name.chars()
+        .flat_map(char::to_lowercase)
+        .map(|c| {
+            LETTERS_WITH_DIACRITICS
+                .iter()
+                .find(|(letters, _)| letters.contains(c))
+                .map_or_else(|| c.to_string(), |(_, base)| base.to_string())
+        })
+        .collect()
+}
+
+/// Whether two names are the same, ignoring case and diacritics
+pub fn names_match(a: &str, b: &str) -> bool {
+    collation_key(a) == collation_key(b)
+}
+
+/// Whether a name contains the searched text, ignoring case and diacritics
+pub fn name_contains(name: &str, search: &str) -> bool {
+    collation_key(name).contains(&collation_key(search))
+}
+
+/// The number of columns a text takes in a terminal, where CJK characters and most emoji take two.
+/// Emoji joined by a zero width joiner (such as 👩‍👩‍👧) are displayed as one, so only the first one is counted.
+pub fn display_width(text: &str) -> usize {
+    let mut width = 0;
+    let mut joined = false;
+    for c in text.chars() {
+        if !joined {
+            width += c.width().unwrap_or(0);
+        }
+        joined = c == '\u{200d}';
+    }
+    width
+}
+
+/// Wrap a text over several lines between its words, so that the lines are at most the given
+/// number of columns wide (unless a word is wider than that).
+pub fn wrap_text(text: &str, width: usize) -> String {
+    let mut lines: Vec<String> = vec![];
+    for word in text.split_whitespace() {
+        match lines.last_mut() {
+            Some(line) if display_width(line) + 1 + display_width(word) <= width => {
+                line.push(' ');
+                line.push_str(word);
+            }
+            _ => lines.push(word.to_string()),
+        }
+    }
+    lines.join("\n")
+}
+
+/// Quote a string as a JSON string, escaping the characters that can't be in one
+pub fn json_string(s: &str) -> String {
+    let mut json = String::from('"');
+    for c in s.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\t' => json.push_str("\\t"),
+            c if c.is_control() => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+/// Percent-encode a string so it can be used in a URL, such as the subject of a mailto link.
+/// Only the unreserved characters of RFC 3986 are left as they are.
+pub fn percent_encode(s: &str) -> String {
+    s.bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
-    use super::find_prev_next_occurences;
-    use chrono::NaiveDate;
+    use super::{
+        find_prev_next_month_occurences, find_prev_next_occurences,
+        find_prev_next_weekday_occurences, Season,
+    };
+    use crate::config::{Hemisphere, LeapDay};
+    use chrono::{NaiveDate, Offset, TimeZone, Weekday};
+    use chrono_tz::America::New_York;
     use test_case::test_case;
 
     #[test]
     fn test_make_date_safe() {
         // Test a leap year
         assert_eq!(
-            super::make_date_safe(2020, 2, 29),
+            super::make_date_safe(2020, 2, 29, LeapDay::Feb28),
+            NaiveDate::from_ymd_opt(2020, 2, 29).unwrap()
+        );
+        assert_eq!(
+            super::make_date_safe(2020, 2, 29, LeapDay::Mar01),
             NaiveDate::from_ymd_opt(2020, 2, 29).unwrap()
         );
         // Test a non-leap year
         assert_eq!(
-            super::make_date_safe(2021, 2, 29),
+            super::make_date_safe(2021, 2, 29, LeapDay::Feb28),
             NaiveDate::from_ymd_opt(2021, 2, 28).unwrap()
         );
+        assert_eq!(
+            super::make_date_safe(2021, 2, 29, LeapDay::Mar01),
+            NaiveDate::from_ymd_opt(2021, 3, 1).unwrap()
+        );
     }
 
     #[test_case(6, 2,
@@ -98,8 +461,283 @@ mod tests {
         expected: Option<(NaiveDate, NaiveDate)>,
     ) {
         assert_eq!(
-            find_prev_next_occurences(birthday_day, birthday_month, date),
+            find_prev_next_occurences(birthday_day, birthday_month, date, LeapDay::Feb28),
+            expected
+        );
+    }
+
+    #[test_case(LeapDay::Feb28, NaiveDate::from_ymd_opt(2023, 2, 1).unwrap(),
+        Some((NaiveDate::from_ymd_opt(2022, 2, 28).unwrap(), NaiveDate::from_ymd_opt(2023, 2, 28).unwrap())) ;
+        "February 28th, before")]
+    #[test_case(LeapDay::Feb28, NaiveDate::from_ymd_opt(2023, 2, 28).unwrap(), None ; "February 28th, on the day")]
+    #[test_case(LeapDay::Mar01, NaiveDate::from_ymd_opt(2023, 2, 1).unwrap(),
+        Some((NaiveDate::from_ymd_opt(2022, 3, 1).unwrap(), NaiveDate::from_ymd_opt(2023, 3, 1).unwrap())) ;
+        "March 1st, before")]
+    #[test_case(LeapDay::Mar01, NaiveDate::from_ymd_opt(2023, 2, 28).unwrap(),
+        Some((NaiveDate::from_ymd_opt(2022, 3, 1).unwrap(), NaiveDate::from_ymd_opt(2023, 3, 1).unwrap())) ;
+        "March 1st, the day before")]
+    #[test_case(LeapDay::Mar01, NaiveDate::from_ymd_opt(2023, 3, 1).unwrap(), None ; "March 1st, on the day")]
+    #[test_case(LeapDay::Mar01, NaiveDate::from_ymd_opt(2024, 2, 1).unwrap(),
+        Some((NaiveDate::from_ymd_opt(2023, 3, 1).unwrap(), NaiveDate::from_ymd_opt(2024, 2, 29).unwrap())) ;
+        "March 1st, next year is a leap year")]
+    fn test_find_prev_next_occurences_leap_day(
+        leap_day: LeapDay,
+        date: NaiveDate,
+        expected: Option<(NaiveDate, NaiveDate)>,
+    ) {
+        assert_eq!(find_prev_next_occurences(29, 2, date, leap_day), expected);
+    }
+
+    #[test]
+    fn test_nth_weekday_of_month() {
+        // Second sunday of may 2024
+        assert_eq!(
+            super::nth_weekday_of_month(2024, 5, Weekday::Sun, 2),
+            NaiveDate::from_ymd_opt(2024, 5, 12).unwrap()
+        );
+        // There is no fifth monday in february 2024, so we get the last one
+        assert_eq!(
+            super::nth_weekday_of_month(2024, 2, Weekday::Mon, 5),
+            NaiveDate::from_ymd_opt(2024, 2, 26).unwrap()
+        );
+    }
+
+    #[test_case(2, Weekday::Sun, 5,
+                NaiveDate::from_ymd_opt(2024, 5, 12).unwrap(),
+                None ;
+                "Occurence is same day as date")]
+    #[test_case(2, Weekday::Sun, 5,
+                NaiveDate::from_ymd_opt(2024, 6, 1).unwrap(),
+                Some((
+                    NaiveDate::from_ymd_opt(2024, 5, 12).unwrap(),
+                    NaiveDate::from_ymd_opt(2025, 5, 11).unwrap(),
+                )) ;
+                "Occurence already happened this year")]
+    #[test_case(4, Weekday::Thu, 11,
+                NaiveDate::from_ymd_opt(2024, 5, 5).unwrap(),
+                Some((
+                    NaiveDate::from_ymd_opt(2023, 11, 23).unwrap(),
+                    NaiveDate::from_ymd_opt(2024, 11, 28).unwrap(),
+                )) ;
+                "Occurence hasn't happened yet this year")]
+    fn test_find_prev_next_weekday_occurences(
+        week_of_month: u8,
+        weekday: Weekday,
+        month: u32,
+        date: NaiveDate,
+        expected: Option<(NaiveDate, NaiveDate)>,
+    ) {
+        assert_eq!(
+            find_prev_next_weekday_occurences(week_of_month, weekday, month, date),
             expected
         );
     }
+
+    #[test_case(0, 1000 ; "Born today")]
+    #[test_case(999, 1000 ; "Day before the milestone")]
+    #[test_case(1000, 2000 ; "Day of the milestone")]
+    #[test_case(12345, 13000 ; "Between milestones")]
+    fn test_next_days_milestone(days_alive: i64, expected: i64) {
+        assert_eq!(super::next_days_milestone(days_alive), expected);
+    }
+
+    #[test_case(0, 10000 ; "Birth")]
+    #[test_case(9999, 10000 ; "Day before the first milestone")]
+    #[test_case(10000, 10000 ; "Day of the first milestone")]
+    #[test_case(10001, 15000 ; "Day after the first milestone")]
+    #[test_case(20000, 20000 ; "Day of a later milestone")]
+    fn test_next_celebrated_milestone(days_alive: i64, expected: i64) {
+        assert_eq!(super::next_celebrated_milestone(days_alive), expected);
+    }
+
+    #[test_case(1, "Garnet")]
+    #[test_case(2, "Amethyst")]
+    #[test_case(3, "Aquamarine")]
+    #[test_case(4, "Diamond")]
+    #[test_case(5, "Emerald")]
+    #[test_case(6, "Pearl")]
+    #[test_case(7, "Ruby")]
+    #[test_case(8, "Peridot")]
+    #[test_case(9, "Sapphire")]
+    #[test_case(10, "Opal")]
+    #[test_case(11, "Topaz")]
+    #[test_case(12, "Turquoise")]
+    fn test_birthstone(month: u32, expected: &str) {
+        assert_eq!(super::birthstone(month), expected);
+    }
+
+    #[test_case(1, '⛄')]
+    #[test_case(2, '💘')]
+    #[test_case(3, '🍀')]
+    #[test_case(4, '🌸')]
+    #[test_case(5, '🌷')]
+    #[test_case(6, '🌞')]
+    #[test_case(7, '🍦')]
+    #[test_case(8, '🍉')]
+    #[test_case(9, '🍎')]
+    #[test_case(10, '🎃')]
+    #[test_case(11, '🍂')]
+    #[test_case(12, '❄')]
+    fn test_month_emoji(month: u32, expected: char) {
+        assert_eq!(super::month_emoji(month), expected);
+    }
+
+    #[test_case(1, "st")]
+    #[test_case(2, "nd")]
+    #[test_case(3, "rd")]
+    #[test_case(4, "th")]
+    #[test_case(11, "th")]
+    #[test_case(12, "th")]
+    #[test_case(13, "th")]
+    #[test_case(21, "st")]
+    #[test_case(22, "nd")]
+    #[test_case(23, "rd")]
+    #[test_case(30, "th")]
+    #[test_case(31, "st")]
+    fn test_ordinal_suffix(day: u32, expected: &str) {
+        assert_eq!(super::ordinal_suffix(day), expected);
+    }
+
+    #[test_case("María", "maria" ; "Acute accent")]
+    #[test_case("ŁUKASZ", "lukasz" ; "Uppercase with a stroke")]
+    #[test_case("Gößmann", "gossmann" ; "Umlaut and sharp s")]
+    #[test_case("Zoë-Ñúñez", "zoe-nunez" ; "Several diacritics")]
+    #[test_case("Юлия", "юлия" ; "Other scripts are only lowercased")]
+    fn test_collation_key(name: &str, expected: &str) {
+        assert_eq!(super::collation_key(name), expected);
+    }
+
+    #[test]
+    fn test_collation_sorts_accented_names() {
+        let mut names = vec!["Mariz", "María", "Émile", "Maria", "Eva", "Zoé"];
+        names.sort_by_cached_key(|name| (super::collation_key(name), name.to_string()));
+        assert_eq!(
+            names,
+            vec!["Émile", "Eva", "Maria", "María", "Mariz", "Zoé"]
+        );
+    }
+
+    #[test_case(NaiveDate::from_ymd_opt(1990, 3, 10).unwrap(), NaiveDate::from_ymd_opt(1993, 5, 15).unwrap(), (3, 2, 5) ; "Years, months and days")]
+    #[test_case(NaiveDate::from_ymd_opt(1990, 3, 10).unwrap(), NaiveDate::from_ymd_opt(1990, 3, 10).unwrap(), (0, 0, 0) ; "Same day")]
+    #[test_case(NaiveDate::from_ymd_opt(1990, 3, 20).unwrap(), NaiveDate::from_ymd_opt(1991, 3, 10).unwrap(), (0, 11, 18) ; "Day of the month before")]
+    #[test_case(NaiveDate::from_ymd_opt(1990, 1, 31).unwrap(), NaiveDate::from_ymd_opt(1990, 3, 1).unwrap(), (0, 1, 1) ; "End of a month")]
+    #[test_case(NaiveDate::from_ymd_opt(2000, 2, 29).unwrap(), NaiveDate::from_ymd_opt(2001, 2, 28).unwrap(), (0, 11, 30) ; "February 29th to a non-leap year")]
+    fn test_years_months_days_between(from: NaiveDate, to: NaiveDate, expected: (u32, u32, u32)) {
+        assert_eq!(super::years_months_days_between(from, to), expected);
+    }
+
+    #[test_case("Alice", "al", true ; "Start of the name")]
+    #[test_case("Alice", "LIC", true ; "Middle of the name, another case")]
+    #[test_case("María José", "maria j", true ; "Accents are ignored")]
+    #[test_case("Alice", "Alan", false ; "Not contained")]
+    #[test_case("Al", "Alice", false ; "Longer than the name")]
+    fn test_name_contains(name: &str, search: &str, expected: bool) {
+        assert_eq!(super::name_contains(name, search), expected);
+    }
+
+    #[test_case("50€", 10, "50€" ; "Short text")]
+    #[test_case("a book and some flowers", 10, "a book and\nsome\nflowers" ; "Several lines")]
+    #[test_case("supercalifragilistic", 10, "supercalifragilistic" ; "Word longer than the width")]
+    #[test_case("  two   spaces ", 10, "two spaces" ; "Extra whitespace")]
+    #[test_case("花束 チョコレート", 10, "花束\nチョコレート" ; "Wide characters")]
+    fn test_wrap_text(text: &str, width: usize, expected: &str) {
+        assert_eq!(super::wrap_text(text, width), expected);
+    }
+
+    #[test_case("Bob", 3 ; "Latin letters")]
+    #[test_case("山田太郎", 8 ; "CJK characters")]
+    #[test_case("🎂 Mom", 6 ; "Emoji")]
+    #[test_case("👩\u{200d}👩\u{200d}👧 Family", 9 ; "Joined emoji")]
+    #[test_case("María", 5 ; "Accent")]
+    fn test_display_width(text: &str, expected: usize) {
+        assert_eq!(super::display_width(text), expected);
+    }
+
+    #[test_case("Error", "\"Error\"" ; "Plain text")]
+    #[test_case("The \"name\"", "\"The \\\"name\\\"\"" ; "Quotes")]
+    #[test_case("C:\\bday\nline", "\"C:\\\\bday\\nline\"" ; "Backslash and newline")]
+    #[test_case("\u{1b}[1m", "\"\\u001b[1m\"" ; "Control character")]
+    #[test_case("María 🎂", "\"María 🎂\"" ; "Unicode is kept")]
+    fn test_json_string(s: &str, expected: &str) {
+        assert_eq!(super::json_string(s), expected);
+    }
+
+    #[test_case("Happy birthday!", "Happy%20birthday%21" ; "Spaces and punctuation")]
+    #[test_case("a-b_c.d~e", "a-b_c.d~e" ; "Unreserved characters")]
+    #[test_case("Joyeux anniversaire Zoé", "Joyeux%20anniversaire%20Zo%C3%A9" ; "Non-ASCII characters")]
+    fn test_percent_encode(s: &str, expected: &str) {
+        assert_eq!(super::percent_encode(s), expected);
+    }
+
+    #[test_case(3, NaiveDate::from_ymd_opt(2024, 1, 15).unwrap(), (NaiveDate::from_ymd_opt(2023, 3, 1).unwrap(), NaiveDate::from_ymd_opt(2024, 3, 1).unwrap()) ; "Later this year")]
+    #[test_case(3, NaiveDate::from_ymd_opt(2024, 3, 20).unwrap(), (NaiveDate::from_ymd_opt(2023, 3, 1).unwrap(), NaiveDate::from_ymd_opt(2024, 3, 1).unwrap()) ; "During the month")]
+    #[test_case(3, NaiveDate::from_ymd_opt(2024, 4, 1).unwrap(), (NaiveDate::from_ymd_opt(2024, 3, 1).unwrap(), NaiveDate::from_ymd_opt(2025, 3, 1).unwrap()) ; "Next year")]
+    fn test_find_prev_next_month_occurences(
+        month: u32,
+        date: NaiveDate,
+        expected: (NaiveDate, NaiveDate),
+    ) {
+        assert_eq!(find_prev_next_month_occurences(month, date), expected);
+    }
+
+    #[test_case(12, Hemisphere::Northern, Season::Winter ; "December in the north")]
+    #[test_case(2, Hemisphere::Northern, Season::Winter ; "February in the north")]
+    #[test_case(3, Hemisphere::Northern, Season::Spring ; "March in the north")]
+    #[test_case(8, Hemisphere::Northern, Season::Summer ; "August in the north")]
+    #[test_case(11, Hemisphere::Northern, Season::Autumn ; "November in the north")]
+    #[test_case(1, Hemisphere::Southern, Season::Summer ; "January in the south")]
+    #[test_case(4, Hemisphere::Southern, Season::Autumn ; "April in the south")]
+    #[test_case(7, Hemisphere::Southern, Season::Winter ; "July in the south")]
+    #[test_case(9, Hemisphere::Southern, Season::Spring ; "September in the south")]
+    fn test_season_of(month: u32, hemisphere: Hemisphere, expected: Season) {
+        assert_eq!(super::season_of(month, hemisphere), expected);
+    }
+
+    #[test_case((2024, 6, 6, 8, 0), (2024, 6, 6, 23, 0), 0 ; "Same day")]
+    #[test_case((2024, 6, 6, 23, 0), (2024, 6, 7, 0, 0), 1 ; "Next day, less than an hour away")]
+    #[test_case((2024, 6, 6, 0, 0), (2024, 6, 8, 23, 0), 2 ; "Two days, almost three days away")]
+    #[test_case((2024, 3, 10, 0, 30), (2024, 3, 11, 0, 0), 1 ; "Next day, across a DST transition")]
+    #[test_case((2024, 3, 9, 0, 0), (2024, 3, 11, 0, 0), 2 ; "Two days, less than 48 hours away because of DST")]
+    fn test_days_between(
+        from: (i32, u32, u32, u32, u32),
+        to: (i32, u32, u32, u32, u32),
+        expected: i64,
+    ) {
+        let from = New_York
+            .with_ymd_and_hms(from.0, from.1, from.2, from.3, from.4, 0)
+            .unwrap();
+        let to = New_York
+            .with_ymd_and_hms(to.0, to.1, to.2, to.3, to.4, 0)
+            .unwrap();
+        assert_eq!(super::days_between(&from, &to), expected);
+    }
+
+    #[test_case((2024, 6, 6, 9, 30), (2024, 6, 6, 9, 30), -4 ; "Normal time")]
+    #[test_case((2024, 3, 10, 2, 30), (2024, 3, 10, 3, 0), -4 ; "Skipped by DST, shifted forward")]
+    #[test_case((2024, 11, 3, 1, 30), (2024, 11, 3, 1, 30), -4 ; "Happens twice because of DST, earliest is used")]
+    fn test_localize(
+        dt: (i32, u32, u32, u32, u32),
+        expected: (i32, u32, u32, u32, u32),
+        offset_hours: i32,
+    ) {
+        let naive = |dt: (i32, u32, u32, u32, u32)| {
+            NaiveDate::from_ymd_opt(dt.0, dt.1, dt.2)
+                .unwrap()
+                .and_hms_opt(dt.3, dt.4, 0)
+                .unwrap()
+        };
+        let localized = super::localize(&New_York, naive(dt));
+        assert_eq!(localized.naive_local(), naive(expected));
+        assert_eq!(
+            localized.offset().fix().local_minus_utc(),
+            offset_hours * 3600
+        );
+    }
+
+    #[test_case(0, Some("Today"))]
+    #[test_case(1, Some("Tomorrow"))]
+    #[test_case(2, None)]
+    fn test_relative_day(days: i64, expected: Option<&str>) {
+        assert_eq!(super::relative_day(days), expected);
+    }
 }